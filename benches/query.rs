@@ -0,0 +1,61 @@
+use std::time::Instant;
+
+use visio::data_gen;
+use visio::utils::data_loader::{Data, Query, RangeQuery, Selection};
+
+const QUERIES: usize = 200;
+const DEFAULT_N: usize = 100_000;
+
+/// Time of `query_2d_scan` and indexed `query_2d` over `sels`.
+fn compare(name: &str, data: &Data, sels: &[Selection]) {
+	let start = Instant::now();
+	let mut found_scan = 0;
+	for sel in sels {
+		let res = data.query_2d_scan(sel);
+		found_scan += res.photo.len() + res.temp.len() + res.flow.len();
+	}
+	let scan = start.elapsed();
+
+	let start = Instant::now();
+	let mut found_index = 0;
+	for sel in sels {
+		let res = data.query_2d(sel);
+		found_index += res.photo.len() + res.temp.len() + res.flow.len();
+	}
+	let index = start.elapsed();
+
+	println!("{}: {} queries, {} / {} records found", name, sels.len(), found_scan, found_index);
+	println!("  scan:  {:?} per query", scan / sels.len() as u32);
+	println!("  index: {:?} per query", index / sels.len() as u32);
+}
+
+/// Compare indexed `Data::query_2d` with linear scan on synthetic data,
+/// records per channel are given by first argument.
+fn main() {
+	let n = std::env::args().skip(1)
+		.find(|a| !a.starts_with('-'))
+		.and_then(|a| a.parse().ok())
+		.unwrap_or(DEFAULT_N);
+	println!("Generating {} records per channel", n);
+	let data = data_gen::generate(n, 1);
+	let ranges = data.ranges();
+	let span = (ranges.timestamp_max - ranges.timestamp_min).max(1);
+
+	let deltas: Vec<Selection> = (0..QUERIES).map(|i| Selection::Delta(Query {
+		timestamp: ranges.timestamp_min + span * i as i64 / QUERIES as i64,
+		timestamp_d: 86_400,
+		deep: (i % 50) as f64,
+		temp_deep_d: 0.8,
+		photo_deep_d: 0.5,
+		flow_deep_d: 2.0
+	})).collect();
+	compare("delta", &data, &deltas);
+
+	let seasons: Vec<Selection> = (0..QUERIES).map(|i| Selection::Range(RangeQuery {
+		timestamp_start: ranges.timestamp_min,
+		timestamp_end: ranges.timestamp_max,
+		deep_min: (i % 50) as f64,
+		deep_max: (i % 50) as f64 + 1.0
+	})).collect();
+	compare("season range", &data, &seasons);
+}
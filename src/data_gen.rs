@@ -0,0 +1,58 @@
+use crate::utils::data_loader::{Data, Point, Photo, Temp, Flow};
+
+const LAT0: f64 = 55.0;
+const LON0: f64 = 37.0;
+const AREA_DEG: f64 = 0.1;
+const DEEP_MAX: f64 = 50.0;
+const TIMESTAMP0: i64 = 1_660_000_000;
+const SEASON: i64 = 90 * 86_400;
+
+/// Deterministic linear congruential generator, good enough for synthetic data.
+struct Lcg(u64);
+
+impl Lcg {
+	fn next_f64(&mut self) -> f64 {
+		self.0 = self.0.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+		(self.0 >> 11) as f64 / (1u64 << 53) as f64
+	}
+
+	fn point(&mut self) -> Point {
+		Point {
+			latitude: LAT0 + self.next_f64() * AREA_DEG,
+			longitude: LON0 + self.next_f64() * AREA_DEG,
+			deep: (self.next_f64() * DEEP_MAX * 2.0).round() / 2.0
+		}
+	}
+
+	fn timestamp(&mut self) -> i64 {
+		TIMESTAMP0 + (self.next_f64() * SEASON as f64) as i64
+	}
+}
+
+/// Random photo, temp and flow records, `n` of each, spread over a season.
+pub fn generate(n: usize, seed: u64) -> Data {
+	let mut rng = Lcg(seed);
+	let mut gen = Data::default();
+	for _ in 0..n {
+		gen.photo.push(Photo {
+			point: rng.point(),
+			timestamp: rng.timestamp(),
+			solar: rng.next_f64() * 1000.0,
+			transparency: (0..8).map(|i| (400.0 + i as f64 * 40.0, rng.next_f64())).collect()
+		});
+		gen.temp.push(Temp {
+			point: rng.point(),
+			timestamp: rng.timestamp(),
+			val: rng.next_f64() * 25.0
+		});
+		gen.flow.push(Flow {
+			point: rng.point(),
+			timestamp: rng.timestamp(),
+			speed: rng.next_f64() * 2.0,
+			dir: rng.next_f64() * 360.0
+		});
+	}
+	let mut data = Data::default();
+	data.add(gen);
+	data
+}
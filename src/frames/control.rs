@@ -1,11 +1,21 @@
 use bevy::prelude::*;
-use bevy_egui::{EguiContext, egui::{self, Align2, Slider}};
+use bevy_egui::{EguiContext, egui::{self, Align2, Slider, Color32, Rect, Pos2, DragValue}};
 use serde::{Deserialize, Serialize};
 
-use crate::utils::data_loader as datal;
+use crate::utils::{data_loader as datal, config::{Config, save_config}};
 use datal::Data;
-use super::GuiState;
+use super::{GuiState, logger::{Log, LogType}, timeline::{self, TimeDisplay, TimePicker}, range_slider::range_slider};
 
+pub const MAX_TIMESTAMP_DELTA: i64 = 30 * 86_400;
+pub const MIN_TIMESTAMP_DELTA: i64 = 1;
+pub const TIME_SLIDER_WIDTH: f32 = 500.0;
+pub const TIME_BAND_COLOR: Color32 = Color32::from_rgba_premultiplied(60, 120, 200, 90);
+pub const TIME_TICK_COLOR: Color32 = Color32::from_rgb(230, 160, 40);
+pub const TIME_TICK_HEIGHT: f32 = 4.0;
+pub const DEEP_SLIDER_HEIGHT: f32 = 300.0;
+pub const DEEP_LEVEL_MARK: f32 = 4.0;
+pub const DEEP_LEVEL_COLORS: [Color32; 3] = [Color32::YELLOW, Color32::from_rgb(255, 69, 0), Color32::from_rgb(0, 255, 255)];
+const DEEP_LEVEL_EPS: f64 = 1e-9;
 pub const MAX_PHOTO_DEEP_DELTA: f64 = 100.0;
 pub const MIN_PHOTO_DEEP_DELTA: f64 = 0.1;
 pub const MAX_TEMP_DEEP_DELTA: f64 = 100.0;
@@ -16,6 +26,8 @@ pub const MIN_FLOW_DEEP_DELTA: f64 = 0.1;
 
 pub struct EventControlDataChanged;
 
+pub struct EventLayersChanged;
+
 #[derive(Deserialize, Serialize, Clone)]
 pub struct Deltas {
 	pub timestamp: i64,
@@ -24,28 +36,195 @@ pub struct Deltas {
 	pub flow_deep: f64
 }
 
+#[derive(Resource, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct LayerVisibility {
+	pub background: bool,
+	pub border: bool,
+	pub photo: bool,
+	pub temp: bool,
+	pub flow: bool,
+	pub labels: bool,
+	pub grid: bool
+}
+
+impl Default for LayerVisibility {
+	fn default() -> Self {
+		Self {
+			background: true,
+			border: true,
+			photo: true,
+			temp: true,
+			flow: true,
+			labels: true,
+			grid: false
+		}
+	}
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaybackStep {
+	/// Advance by `rate` seconds of data per real second.
+	Rate,
+	/// Jump to next record timestamp `records_per_second` times per real second.
+	Record
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaybackEnd {
+	Stop,
+	Loop,
+	PingPong
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct PlaybackConfig {
+	pub step: PlaybackStep,
+	pub rate: f64,
+	pub records_per_second: f64,
+	pub end: PlaybackEnd
+}
+
+impl Default for PlaybackConfig {
+	fn default() -> Self {
+		Self {
+			step: PlaybackStep::Rate,
+			rate: 3600.0,
+			records_per_second: 2.0,
+			end: PlaybackEnd::Loop
+		}
+	}
+}
+
+#[derive(Resource)]
+pub struct Playback {
+	pub config: PlaybackConfig,
+	pub playing: bool,
+	pub forward: bool,
+	/// Fraction of step not yet applied.
+	acc: f64
+}
+
+impl Playback {
+	pub fn new(config: &PlaybackConfig) -> Self {
+		Self {
+			config: config.clone(),
+			playing: false,
+			forward: true,
+			acc: 0.0
+		}
+	}
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct DeepLevelsConfig {
+	/// Depths closer than this are one level.
+	pub tolerance: f64,
+	/// Snap deep slider to nearest level.
+	pub snap: bool
+}
+
+impl Default for DeepLevelsConfig {
+	fn default() -> Self {
+		Self {
+			tolerance: 0.1,
+			snap: true
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SelectionMode {
+	/// Center timestamp and deep with symmetric deltas.
+	Delta,
+	/// Explicit time and depth ranges.
+	Range
+}
+
 #[derive(Resource)]
 pub struct Control {
 	pub ranges: datal::Ranges,
+	pub mode: SelectionMode,
+	pub range: datal::RangeQuery,
 	pub deltas: Deltas,
 	pub deep: f64,
-	pub timestamp: i64
+	pub timestamp: i64,
+	/// Sorted distinct record timestamps, for ticks and stepping.
+	pub timestamps: Vec<i64>,
+	/// Measured depth levels of photo, temp and flow.
+	pub channel_levels: [Vec<f64>; 3],
+	/// Levels of all channels together, for snapping and stepping.
+	pub levels: Vec<f64>
 }
 
 impl Control {
 	pub fn new(deltas: &Deltas) -> Self {
 		Self {
 			ranges: datal::Ranges::default(),
+			mode: SelectionMode::Delta,
+			range: datal::RangeQuery::default(),
 			deltas: deltas.clone(),
 			deep: 0.0,
-			timestamp: 0
+			timestamp: 0,
+			timestamps: Vec::new(),
+			channel_levels: [Vec::new(), Vec::new(), Vec::new()],
+			levels: Vec::new()
+		}
+	}
+
+	pub fn query(&self) -> datal::Selection {
+		match self.mode {
+			SelectionMode::Delta => datal::Selection::Delta(datal::Query {
+				timestamp: self.timestamp,
+				timestamp_d: self.deltas.timestamp,
+				deep: self.deep,
+				temp_deep_d: self.deltas.temp_deep,
+				photo_deep_d: self.deltas.photo_deep,
+				flow_deep_d: self.deltas.flow_deep
+			}),
+			SelectionMode::Range => datal::Selection::Range(self.range.clone())
+		}
+	}
+
+	/// Range covering current delta window, used when switching to range mode.
+	fn delta_range(&self) -> datal::RangeQuery {
+		let deep_d = self.deltas.photo_deep.max(self.deltas.temp_deep).max(self.deltas.flow_deep);
+		datal::RangeQuery {
+			timestamp_start: (self.timestamp - self.deltas.timestamp).max(self.ranges.timestamp_min),
+			timestamp_end: (self.timestamp + self.deltas.timestamp).min(self.ranges.timestamp_max),
+			deep_min: (self.deep - deep_d).max(self.ranges.deep_min),
+			deep_max: (self.deep + deep_d).min(self.ranges.deep_max)
 		}
 	}
 }
 
-pub fn update_ranges(data: Res<Data>, mut ctld: ResMut<Control>) {
+pub fn nearest_level(levels: &[f64], deep: f64) -> Option<f64> {
+	levels.iter().copied().min_by(|a, b| (a - deep).abs().total_cmp(&(b - deep).abs()))
+}
+
+/// Next level above (`up`) or below current deep.
+pub fn step_level(levels: &[f64], deep: f64, up: bool) -> Option<f64> {
+	if up {
+		levels.iter().copied().find(|l| *l > deep + DEEP_LEVEL_EPS)
+	} else {
+		levels.iter().rev().copied().find(|l| *l < deep - DEEP_LEVEL_EPS)
+	}
+}
+
+pub fn update_ranges(data: Res<Data>, config: Res<Config>, mut ctld: ResMut<Control>, mut evw: EventWriter<EventControlDataChanged>) {
 	if data.is_changed() {
 		ctld.ranges = data.ranges();
+		ctld.timestamps = data.timestamps();
+		let tol = config.deep_levels.tolerance;
+		ctld.channel_levels = [
+			data.deep_levels(datal::Channel::Photo, tol),
+			data.deep_levels(datal::Channel::Temp, tol),
+			data.deep_levels(datal::Channel::Flow, tol)
+		];
+		let mut all: Vec<f64> = ctld.channel_levels.iter().flatten().copied().collect();
+		all.sort_by(|a, b| a.total_cmp(b));
+		ctld.levels = datal::cluster_levels(&all, tol);
 		if ctld.deep > ctld.ranges.deep_max {
 			ctld.deep = ctld.ranges.deep_max;
 		}
@@ -58,19 +237,268 @@ pub fn update_ranges(data: Res<Data>, mut ctld: ResMut<Control>) {
 		if ctld.timestamp < ctld.ranges.timestamp_min {
 			ctld.timestamp = ctld.ranges.timestamp_min;
 		}
+		let r = &ctld.ranges;
+		let range = datal::RangeQuery {
+			timestamp_start: ctld.range.timestamp_start.clamp(r.timestamp_min, r.timestamp_max),
+			timestamp_end: ctld.range.timestamp_end.clamp(r.timestamp_min, r.timestamp_max),
+			deep_min: ctld.range.deep_min.clamp(r.deep_min, r.deep_max),
+			deep_max: ctld.range.deep_max.clamp(r.deep_min, r.deep_max)
+		};
+		ctld.range = range;
+		evw.send(EventControlDataChanged);
 	}
 }
 
-pub fn show(mut cmd: Commands, mut ctx: ResMut<EguiContext>, mut ctld: ResMut<Control>, mut evw: EventWriter<EventControlDataChanged>) {
+pub fn playback(time: Res<Time>, mut pb: ResMut<Playback>, mut ctld: ResMut<Control>, mut evw: EventWriter<EventControlDataChanged>) {
+	if !pb.playing {
+		return;
+	}
+	let (min, max) = (ctld.ranges.timestamp_min, ctld.ranges.timestamp_max);
+	if min >= max {
+		pb.playing = false;
+		return;
+	}
+	let dt = time.delta_seconds_f64();
+	let mut next = Some(ctld.timestamp);
+	match pb.config.step {
+		PlaybackStep::Rate => {
+			pb.acc += dt * pb.config.rate;
+			let whole = pb.acc.trunc();
+			pb.acc -= whole;
+			let step = if pb.forward {whole as i64} else {-(whole as i64)};
+			let ts = ctld.timestamp + step;
+			if ts > max || ts < min {
+				next = None;
+			} else {
+				next = Some(ts);
+			}
+		},
+		PlaybackStep::Record => {
+			pb.acc += dt * pb.config.records_per_second;
+			while pb.acc >= 1.0 {
+				pb.acc -= 1.0;
+				next = match next {
+					Some(ts) => timeline::step_record(&ctld.timestamps, ts, pb.forward),
+					None => None
+				};
+			}
+		}
+	}
+	let next = match next {
+		Some(ts) => ts,
+		None => {
+			pb.acc = 0.0;
+			let edge = if pb.forward {max} else {min};
+			match pb.config.end {
+				PlaybackEnd::Stop => {
+					pb.playing = false;
+					edge
+				},
+				PlaybackEnd::Loop => if pb.forward {min} else {max},
+				PlaybackEnd::PingPong => {
+					pb.forward = !pb.forward;
+					edge
+				}
+			}
+		}
+	};
+	if next != ctld.timestamp {
+		ctld.timestamp = next;
+		evw.send(EventControlDataChanged);
+	}
+}
+
+fn show_playback(ui: &mut egui::Ui, pb: &mut Playback) {
+	ui.horizontal(|ui| {
+		let icon = if pb.playing {"⏸"} else {"▶"};
+		if ui.button(icon).clicked() {
+			pb.playing = !pb.playing;
+		}
+		ui.checkbox(&mut pb.forward, "Forward");
+		ui.separator();
+		ui.selectable_value(&mut pb.config.step, PlaybackStep::Rate, "Rate");
+		ui.selectable_value(&mut pb.config.step, PlaybackStep::Record, "By record");
+		match pb.config.step {
+			PlaybackStep::Rate => ui.add(DragValue::new(&mut pb.config.rate).clamp_range(1.0..=1.0e7).suffix(" s/s")),
+			PlaybackStep::Record => ui.add(DragValue::new(&mut pb.config.records_per_second).clamp_range(0.1..=100.0).suffix(" rec/s"))
+		};
+		ui.separator();
+		ui.selectable_value(&mut pb.config.end, PlaybackEnd::Stop, "Once");
+		ui.selectable_value(&mut pb.config.end, PlaybackEnd::Loop, "Loop");
+		ui.selectable_value(&mut pb.config.end, PlaybackEnd::PingPong, "Ping-pong");
+	});
+}
+
+/// Highlight [timestamp - delta, timestamp + delta] over time slider rail.
+fn paint_time_band(ui: &egui::Ui, rect: Rect, ctld: &Control) {
+	let span = (ctld.ranges.timestamp_max - ctld.ranges.timestamp_min) as f32;
+	if span <= 0.0 {
+		return;
+	}
+	// Same inset as slider handle, see egui Slider::position_range.
+	let inset = rect.height() / 2.5;
+	let left = rect.left() + inset;
+	let width = rect.width() - 2.0 * inset;
+	let to_x = |ts: i64| {
+		let t = ((ts - ctld.ranges.timestamp_min) as f32 / span).clamp(0.0, 1.0);
+		left + t * width
+	};
+	let band = Rect::from_min_max(
+		Pos2::new(to_x(ctld.timestamp - ctld.deltas.timestamp), rect.top()),
+		Pos2::new(to_x(ctld.timestamp + ctld.deltas.timestamp), rect.bottom())
+	);
+	ui.painter().rect_filled(band, 2.0, TIME_BAND_COLOR);
+}
+
+/// Mark depth levels of each channel left of vertical slider rail.
+fn paint_deep_levels(ui: &egui::Ui, rect: Rect, ctld: &Control) {
+	let span = ctld.ranges.deep_max - ctld.ranges.deep_min;
+	if span <= 0.0 {
+		return;
+	}
+	let inset = rect.width() / 2.5;
+	let bottom = rect.bottom() - inset;
+	let height = rect.height() - 2.0 * inset;
+	for (i, levels) in ctld.channel_levels.iter().enumerate() {
+		let x = rect.left() - DEEP_LEVEL_MARK * (i as f32 + 1.0);
+		for l in levels {
+			let t = ((l - ctld.ranges.deep_min) / span).clamp(0.0, 1.0) as f32;
+			let y = bottom - t * height;
+			ui.painter().line_segment([Pos2::new(x, y), Pos2::new(x + DEEP_LEVEL_MARK, y)], (1.0, DEEP_LEVEL_COLORS[i]));
+		}
+	}
+}
+
+/// Mark record timestamps under time slider rail.
+fn paint_time_ticks(ui: &egui::Ui, rect: Rect, ctld: &Control) {
+	let span = (ctld.ranges.timestamp_max - ctld.ranges.timestamp_min) as f32;
+	if span <= 0.0 {
+		return;
+	}
+	let inset = rect.height() / 2.5;
+	let left = rect.left() + inset;
+	let width = rect.width() - 2.0 * inset;
+	let to_x = |ts: i64| {
+		let t = ((ts - ctld.ranges.timestamp_min) as f32 / span).clamp(0.0, 1.0);
+		left + t * width
+	};
+	let mut last_x = f32::MIN;
+	for ts in &ctld.timestamps {
+		let x = to_x(*ts).round();
+		if x == last_x {
+			continue;
+		}
+		last_x = x;
+		ui.painter().line_segment(
+			[Pos2::new(x, rect.bottom() - TIME_TICK_HEIGHT), Pos2::new(x, rect.bottom())],
+			(1.0, TIME_TICK_COLOR)
+		);
+	}
+}
+
+pub fn show(
+	mut cmd: Commands,
+	mut ctx: ResMut<EguiContext>,
+	mut ctld: ResMut<Control>,
+	mut layers: ResMut<LayerVisibility>,
+	mut picker: ResMut<TimePicker>,
+	mut pb: ResMut<Playback>,
+	mut config: ResMut<Config>,
+	mut evw: EventWriter<EventControlDataChanged>,
+	mut evw_layers: EventWriter<EventLayersChanged>
+) {
 	egui::Area::new("TOP_CONTROL").anchor(Align2::CENTER_TOP, egui::Vec2::default()).show(ctx.ctx_mut(), |ui| {
+		let mut time_display = config.time_display;
 		ui.horizontal(|ui| {
 			let timestamp_max = ctld.ranges.timestamp_max;
 			let timestamp_min = ctld.ranges.timestamp_min;
-			if ui.add(Slider::new(&mut ctld.timestamp, timestamp_min..=timestamp_max)).changed() {
-				evw.send(EventControlDataChanged);
+			match ctld.mode {
+				SelectionMode::Delta => {
+					if ui.button("⏮").on_hover_text("Previous record").clicked() {
+						if let Some(ts) = timeline::step_record(&ctld.timestamps, ctld.timestamp, false) {
+							ctld.timestamp = ts;
+							evw.send(EventControlDataChanged);
+						}
+					}
+					ui.spacing_mut().slider_width = TIME_SLIDER_WIDTH;
+					let resp = ui.add(Slider::new(&mut ctld.timestamp, timestamp_min..=timestamp_max).show_value(false));
+					if resp.changed() {
+						evw.send(EventControlDataChanged);
+					}
+					paint_time_band(ui, resp.rect, &ctld);
+					paint_time_ticks(ui, resp.rect, &ctld);
+					if ui.button("⏭").on_hover_text("Next record").clicked() {
+						if let Some(ts) = timeline::step_record(&ctld.timestamps, ctld.timestamp, true) {
+							ctld.timestamp = ts;
+							evw.send(EventControlDataChanged);
+						}
+					}
+					ui.label(time_display.format(ctld.timestamp));
+				},
+				SelectionMode::Range => {
+					let mut lo = ctld.range.timestamp_start as f64;
+					let mut hi = ctld.range.timestamp_end as f64;
+					let resp = range_slider(ui, &mut lo, &mut hi, timestamp_min as f64..=timestamp_max as f64, false, TIME_SLIDER_WIDTH);
+					if resp.changed() {
+						ctld.range.timestamp_start = lo.round() as i64;
+						ctld.range.timestamp_end = hi.round() as i64;
+						evw.send(EventControlDataChanged);
+					}
+					paint_time_ticks(ui, resp.rect, &ctld);
+					ui.label(format!("{} — {}", time_display.format(ctld.range.timestamp_start), time_display.format(ctld.range.timestamp_end)));
+				}
+			}
+			if ui.button("📅").on_hover_text("Go to time").clicked() {
+				picker.value = time_display.naive_at(ctld.timestamp);
 			}
+			ui.selectable_value(&mut time_display, TimeDisplay::Utc, "UTC");
+			ui.selectable_value(&mut time_display, TimeDisplay::Local, "Local");
 		});
+		if time_display != config.time_display {
+			config.time_display = time_display;
+			if let Err(e) = save_config(&config) {
+				cmd.spawn(Log::new(LogType::Error, &format!("Fail to save config: {}", e)));
+			}
+		}
+		show_playback(ui, &mut pb);
+		let mut lv = *layers;
 		ui.horizontal(|ui| {
+			ui.checkbox(&mut lv.background, "Background");
+			ui.checkbox(&mut lv.border, "Border");
+			ui.checkbox(&mut lv.photo, "Photo");
+			ui.checkbox(&mut lv.temp, "Temp");
+			ui.checkbox(&mut lv.flow, "Flow");
+			ui.checkbox(&mut lv.labels, "Labels");
+			ui.checkbox(&mut lv.grid, "Grid");
+		});
+		if lv != *layers {
+			*layers = lv;
+			config.layers = lv;
+			if let Err(e) = save_config(&config) {
+				cmd.spawn(Log::new(LogType::Error, &format!("Fail to save config: {}", e)));
+			}
+			evw_layers.send(EventLayersChanged);
+		}
+		ui.horizontal(|ui| {
+			let mut mode = ctld.mode;
+			ui.selectable_value(&mut mode, SelectionMode::Delta, "Delta");
+			ui.selectable_value(&mut mode, SelectionMode::Range, "Range");
+			if mode != ctld.mode {
+				if mode == SelectionMode::Range {
+					ctld.range = ctld.delta_range();
+				}
+				ctld.mode = mode;
+				evw.send(EventControlDataChanged);
+			}
+			ui.separator();
+			if ctld.mode == SelectionMode::Range {
+				ui.label(format!("Deep: {:.2} — {:.2}", ctld.range.deep_min, ctld.range.deep_max));
+				return;
+			}
+			if ui.add(Slider::new(&mut ctld.deltas.timestamp, MIN_TIMESTAMP_DELTA..=MAX_TIMESTAMP_DELTA).logarithmic(true).custom_formatter(|v, _| timeline::format_duration(v as i64)).text("Time delta:")).changed() {
+				evw.send(EventControlDataChanged);
+			}
+			ui.separator();
 			if ui.add(Slider::new(&mut ctld.deltas.photo_deep, MIN_PHOTO_DEEP_DELTA..=MAX_PHOTO_DEEP_DELTA).text("Photo deep delta:")).changed() {
 				evw.send(EventControlDataChanged);
 			}
@@ -89,9 +517,79 @@ pub fn show(mut cmd: Commands, mut ctx: ResMut<EguiContext>, mut ctld: ResMut<Co
 		ui.vertical(|ui| {
 			let deep_max = ctld.ranges.deep_max;
 			let deep_min = ctld.ranges.deep_min;
-			if ui.add(Slider::new(&mut ctld.deep, deep_min..=deep_max).orientation(egui::SliderOrientation::Vertical)).changed() {
-				evw.send(EventControlDataChanged);
+			match ctld.mode {
+				SelectionMode::Delta => {
+					if ui.button("▲").on_hover_text("Next level").clicked() {
+						if let Some(l) = step_level(&ctld.levels, ctld.deep, true) {
+							ctld.deep = l;
+							evw.send(EventControlDataChanged);
+						}
+					}
+					ui.spacing_mut().slider_width = DEEP_SLIDER_HEIGHT;
+					let resp = ui.add(Slider::new(&mut ctld.deep, deep_min..=deep_max).orientation(egui::SliderOrientation::Vertical).show_value(false));
+					if resp.changed() {
+						if config.deep_levels.snap {
+							if let Some(l) = nearest_level(&ctld.levels, ctld.deep) {
+								ctld.deep = l;
+							}
+						}
+						evw.send(EventControlDataChanged);
+					}
+					paint_deep_levels(ui, resp.rect, &ctld);
+					if ui.button("▼").on_hover_text("Previous level").clicked() {
+						if let Some(l) = step_level(&ctld.levels, ctld.deep, false) {
+							ctld.deep = l;
+							evw.send(EventControlDataChanged);
+						}
+					}
+					ui.label(format!("{:.2}", ctld.deep));
+				},
+				SelectionMode::Range => {
+					let mut lo = ctld.range.deep_min;
+					let mut hi = ctld.range.deep_max;
+					let resp = range_slider(ui, &mut lo, &mut hi, deep_min..=deep_max, true, DEEP_SLIDER_HEIGHT);
+					if resp.changed() {
+						ctld.range.deep_min = lo;
+						ctld.range.deep_max = hi;
+						evw.send(EventControlDataChanged);
+					}
+					paint_deep_levels(ui, resp.rect, &ctld);
+				}
 			}
 		});
 	});
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const LEVELS: [f64; 3] = [0.0, 5.0, 10.0];
+
+	#[test]
+	fn nearest() {
+		assert_eq!(nearest_level(&[], 3.0), None);
+		assert_eq!(nearest_level(&LEVELS, -4.0), Some(0.0));
+		assert_eq!(nearest_level(&LEVELS, 3.0), Some(5.0));
+		assert_eq!(nearest_level(&LEVELS, 7.0), Some(5.0));
+		assert_eq!(nearest_level(&LEVELS, 25.0), Some(10.0));
+	}
+
+	#[test]
+	fn step_up() {
+		assert_eq!(step_level(&LEVELS, -1.0, true), Some(0.0));
+		assert_eq!(step_level(&LEVELS, 0.0, true), Some(5.0));
+		assert_eq!(step_level(&LEVELS, 5.0 - 1e-12, true), Some(10.0));
+		assert_eq!(step_level(&LEVELS, 10.0, true), None);
+		assert_eq!(step_level(&LEVELS, 12.0, true), None);
+	}
+
+	#[test]
+	fn step_down() {
+		assert_eq!(step_level(&LEVELS, 12.0, false), Some(10.0));
+		assert_eq!(step_level(&LEVELS, 10.0, false), Some(5.0));
+		assert_eq!(step_level(&LEVELS, 5.0 + 1e-12, false), Some(0.0));
+		assert_eq!(step_level(&LEVELS, 0.0, false), None);
+		assert_eq!(step_level(&LEVELS, -1.0, false), None);
+		assert_eq!(step_level(&[], 1.0, false), None);
+	}
+}
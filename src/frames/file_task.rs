@@ -0,0 +1,199 @@
+use std::{path::PathBuf, sync::Arc};
+use bevy::{prelude::*, tasks::{AsyncComputeTaskPool, Task}};
+use bevy_egui::{egui::{self, Align2, Color32}, EguiContext};
+use futures_lite::future;
+
+use crate::utils::{data_loader as datal, config::Config, progress::Progress, data_error::DataError, import::ImportOptions};
+use datal::Data;
+
+use super::{GuiState, logger::{Log, LogType}, report::ValidationReport};
+
+const MODAL_DIM_COLOR: Color32 = Color32::from_black_alpha(160);
+const PROGRESS_WIDTH: f32 = 300.0;
+
+enum Job {
+	Load(Task<Result<(Data, Vec<DataError>), DataError>>, Box<ImportOptions>),
+	Save(Task<Result<Vec<String>, DataError>>)
+}
+
+/// Sheet to channel assignment asked from user when sheets of profile are not in book.
+pub struct SheetPick {
+	pub path: PathBuf,
+	pub available: Vec<String>,
+	pub options: ImportOptions,
+	/// Store picked sheets in selected profile.
+	pub save: bool
+}
+
+/// Channel of per channel CSV asked from user when file name does not tell it.
+pub struct ChannelPick {
+	pub path: PathBuf,
+	/// Picked channel is in `options.channel`.
+	pub options: ImportOptions
+}
+
+/// Load or save of data file running on `AsyncComputeTaskPool`.
+#[derive(Component)]
+pub struct FileTask {
+	path: PathBuf,
+	progress: Arc<Progress>,
+	job: Job
+}
+
+pub fn spawn_load(cmd: &mut Commands, path: PathBuf, options: ImportOptions) {
+	let progress = Arc::new(Progress::default());
+	let task_progress = progress.clone();
+	let task_path = path.clone();
+	let task_options = options.clone();
+	let task = AsyncComputeTaskPool::get().spawn(async move {
+		datal::load_data(&task_path, &task_options, &task_progress)
+	});
+	cmd.spawn(FileTask {
+		path: path,
+		progress: progress,
+		job: Job::Load(task, Box::new(options))
+	});
+}
+
+/// Save snapshot of data, taken by caller with `Data::to_file`.
+pub fn spawn_save(cmd: &mut Commands, path: PathBuf, file: datal::DataFile) {
+	let progress = Arc::new(Progress::default());
+	progress.stage("Encode", 0);
+	let task_path = path.clone();
+	let task = AsyncComputeTaskPool::get().spawn(async move {
+		datal::save_data(&task_path, &file)
+	});
+	cmd.spawn(FileTask {
+		path: path,
+		progress: progress,
+		job: Job::Save(task)
+	});
+}
+
+/// Modal window with progress of running file task, other windows are not
+/// shown while `GuiState::Busy`, see `not_busy`.
+pub fn show(mut ctx: ResMut<EguiContext>, tasks: Query<&FileTask>) {
+	if tasks.is_empty() {
+		return;
+	}
+	let ctx = ctx.ctx_mut();
+	let screen = ctx.input().screen_rect();
+	ctx.layer_painter(egui::LayerId::new(egui::Order::Background, egui::Id::new("FILE_TASK_DIM")))
+		.rect_filled(screen, 0.0, MODAL_DIM_COLOR);
+	for t in &tasks {
+		let title = match t.job {
+			Job::Load(..) => "LOADING",
+			Job::Save(_) => "SAVING"
+		};
+		egui::Window::new(title)
+		.anchor(Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+		.collapsible(false)
+		.resizable(false)
+		.show(ctx, |ui| {
+			ui.label(t.path.display().to_string());
+			let (stage, done, total) = t.progress.get();
+			if total > 0 {
+				ui.label(format!("{}: {} / {} rows", stage, done, total));
+				ui.add(egui::ProgressBar::new(done as f32 / total as f32).desired_width(PROGRESS_WIDTH).show_percentage());
+			} else {
+				ui.horizontal(|ui| {
+					ui.spinner();
+					ui.label(stage);
+				});
+			}
+			if let Job::Load(..) = t.job {
+				if t.progress.is_cancelled() {
+					ui.label("Cancelling...");
+				} else
+				if ui.button("Cancel").clicked() {
+					t.progress.cancel();
+				}
+			}
+		});
+	}
+}
+
+/// Poll file tasks, merge loaded data and log results.
+pub fn finish(mut cmd: Commands, mut gst: ResMut<GuiState>, mut data: ResMut<Data>, mut report: ResMut<ValidationReport>, config: Res<Config>, mut tasks: Query<(Entity, &mut FileTask)>) {
+	for (e, t) in &mut tasks {
+		let t = t.into_inner();
+		let cancelled = t.progress.is_cancelled();
+		let mut state = GuiState::Normal;
+		match &mut t.job {
+			Job::Load(task, options) => {
+				let res = match future::block_on(future::poll_once(task)) {
+					Some(res) => res,
+					None => continue
+				};
+				match res {
+					_ if cancelled => {
+						cmd.spawn(Log::new(LogType::Info, &format!("Loading of {:?} cancelled", t.path)));
+					},
+					Ok((data_add, problems)) => {
+						if !options.append {
+							data.clear();
+						}
+						let added = data.add(data_add);
+						let msg = format!("Data success loaded from {}: photos: {}, temps: {}, flows: {}", t.path.display(), added.0, added.1, added.2);
+						cmd.spawn(Log::new(LogType::Info, &msg));
+						if !problems.is_empty() {
+							cmd.spawn(Log::new(LogType::Warn, &format!("Skipped {} bad rows, see validation report", problems.len())));
+							*report = ValidationReport {
+								path: t.path.clone(),
+								problems: problems,
+								open: true
+							};
+						}
+						if config.border.flag_outside {
+							let outside = data.outside_border();
+							if outside.0 + outside.1 + outside.2 > 0 {
+								let msg = format!("Measurements outside border: photos: {}, temps: {}, flows: {}", outside.0, outside.1, outside.2);
+								cmd.spawn(Log::new(LogType::Warn, &msg));
+							}
+						}
+					},
+					Err(DataError::UnmatchedSheets(available)) => {
+						cmd.spawn(Log::new(LogType::Warn, "Sheets of import profile not found, pick sheets"));
+						state = GuiState::PickSheets(SheetPick {
+							path: t.path.clone(),
+							available: available,
+							options: options.as_ref().clone(),
+							save: false
+						});
+					},
+					Err(DataError::UnknownChannel(name)) => {
+						cmd.spawn(Log::new(LogType::Warn, &format!("Channel of '{}' unknown, pick channel", name)));
+						let mut options = options.as_ref().clone();
+						options.channel = Some(datal::Channel::Photo);
+						state = GuiState::PickChannel(ChannelPick {
+							path: t.path.clone(),
+							options: options
+						});
+					},
+					Err(e) => {
+						cmd.spawn(Log::new(LogType::Error, &format!("Fail to load data from file: {}", e)));
+					}
+				}
+			},
+			Job::Save(task) => {
+				let res = match future::block_on(future::poll_once(task)) {
+					Some(res) => res,
+					None => continue
+				};
+				match res {
+					Ok(warnings) => {
+						for w in warnings {
+							cmd.spawn(Log::new(LogType::Warn, &w));
+						}
+						cmd.spawn(Log::new(LogType::Info, &format!("Data success saved to {}", t.path.display())));
+					},
+					Err(e) => {
+						cmd.spawn(Log::new(LogType::Error, &format!("Fail to save data to file: {}", e)));
+					}
+				}
+			}
+		}
+		cmd.entity(e).despawn();
+		*gst = state;
+	}
+}
@@ -2,12 +2,61 @@ use bevy::prelude::*;
 use bevy_egui::{egui::{self, Align2}, EguiContext};
 use egui_file::FileDialog;
 
-use crate::utils::data_loader as datal;
+use crate::utils::{data_loader as datal, config::{Config, save_config}, import::{ImportMode, ImportProfile, SourceTimezone, NumericTime, COLUMN_FIELDS, SHEET_FIELDS, CHANNELS}};
+use crate::repr_2d::{Shown, camera::EventFitToData, probe::CoordFormat};
 use datal::Data;
 
-use super::{GuiState, logger::{LogType, Log}};
+use super::{GuiState, logger::{LogType, Log}, file_task};
 
-pub fn show(mut cmd: Commands, mut gst: ResMut<GuiState>, mut data: ResMut<Data>, mut ctx: ResMut<EguiContext>) {
+/// Minutes east of UTC.
+const MAX_UTC_OFFSET: i32 = 14 * 60;
+
+fn format_utc_offset(minutes: i32) -> String {
+    let sign = if minutes < 0 {'-'} else {'+'};
+    format!("UTC{}{:02}:{:02}", sign, minutes.abs() / 60, minutes.abs() % 60)
+}
+
+/// Edit buffer of import profile, aliases joined by comma.
+#[derive(Default)]
+pub struct ProfileEditor {
+    loaded: Option<String>,
+    name: String,
+    sheets: Vec<String>,
+    columns: Vec<String>,
+    timezone: SourceTimezone,
+    utc_offset: i32,
+    numeric_time: NumericTime
+}
+
+impl ProfileEditor {
+    fn load(&mut self, profile: &ImportProfile) {
+        self.loaded = Some(profile.name.clone());
+        self.name = profile.name.clone();
+        self.sheets = profile.sheets.fields().iter().map(|f| f.to_string()).collect();
+        self.columns = profile.columns.fields().iter().map(|f| f.join(", ")).collect();
+        self.timezone = profile.timezone;
+        self.utc_offset = profile.utc_offset;
+        self.numeric_time = profile.numeric_time;
+    }
+
+    fn profile(&self) -> ImportProfile {
+        let mut profile = ImportProfile::default();
+        profile.name = String::from(self.name.trim());
+        for (field, text) in profile.sheets.fields_mut().into_iter().zip(&self.sheets) {
+            *field = String::from(text.trim());
+        }
+        for (field, text) in profile.columns.fields_mut().into_iter().zip(&self.columns) {
+            *field = text.split(',').map(|a| String::from(a.trim())).filter(|a| !a.is_empty()).collect();
+        }
+        profile.timezone = self.timezone;
+        profile.utc_offset = self.utc_offset;
+        profile.numeric_time = self.numeric_time;
+        profile
+    }
+}
+
+pub fn show(mut cmd: Commands, mut gst: ResMut<GuiState>, mut data: ResMut<Data>, mut config: ResMut<Config>, mut ctx: ResMut<EguiContext>, mut evw_fit: EventWriter<EventFitToData>, mut editor: Local<ProfileEditor>, shown: Res<Shown>) {
+    let export_shown = matches!(*gst, GuiState::ExportShown(_));
 	match gst.as_mut() {
         GuiState::Normal => {
             egui::Window::new("MENU").anchor(Align2::LEFT_TOP, egui::vec2(0.0, 0.0)).show(ctx.ctx_mut(), |ui| {
@@ -24,6 +73,21 @@ pub fn show(mut cmd: Commands, mut gst: ResMut<GuiState>, mut data: ResMut<Data>
                 if ui.button("Save Data").clicked() {
                     *gst = GuiState::SaveData(FileDialog::save_file(None));
                 }
+                if ui.add_enabled(!shown.stale && !data.is_changed(), egui::Button::new("Export Shown")).clicked() {
+                    *gst = GuiState::ExportShown(FileDialog::save_file(None));
+                }
+                if ui.button("Fit to Data").clicked() {
+                    evw_fit.send(EventFitToData);
+                }
+                let mut coord_format = config.coord_format;
+                ui.horizontal(|ui| {
+                    ui.label("Coords:");
+                    ui.selectable_value(&mut coord_format, CoordFormat::Decimal, "Deg");
+                    ui.selectable_value(&mut coord_format, CoordFormat::Dms, "DMS");
+                });
+                if coord_format != config.coord_format {
+                    config.coord_format = coord_format;
+                }
             });
         },
         GuiState::OpenFile(fdialog) => {
@@ -40,17 +104,8 @@ pub fn show(mut cmd: Commands, mut gst: ResMut<GuiState>, mut data: ResMut<Data>
                     println!("OpenFie::Selected");
                     match fdialog.path() {
                         Some(path) => {
-                            match datal::load_data(&path) {
-                                Ok(data_add) => {
-                                    let added = data.add(data_add);
-                                    let msg = format!("Data success loaded: photos: {}, temps: {}, flows: {}", added.0, added.1, added.2);
-                                    cmd.spawn(Log::new(LogType::Info, &msg));
-                                },
-                                Err(e) => {
-									cmd.spawn(Log::new(LogType::Error, &format!("Fail to load data from file: {}", e)));
-								}
-                            }
-                            *gst = GuiState::Normal;
+                            file_task::spawn_load(&mut cmd, path, config.import.options());
+                            *gst = GuiState::Busy;
                         },
                         None => ()
                     }
@@ -58,6 +113,7 @@ pub fn show(mut cmd: Commands, mut gst: ResMut<GuiState>, mut data: ResMut<Data>
                 egui_file::State::Open => {
                     fdialog.open();
                     fdialog.show(ctx.ctx_mut());
+                    show_import_options(&mut cmd, &mut config, &mut editor, ctx.ctx_mut());
                 }
             }
         },
@@ -68,10 +124,72 @@ pub fn show(mut cmd: Commands, mut gst: ResMut<GuiState>, mut data: ResMut<Data>
                 }
             });
         },
-        GuiState::SaveData(fdialog) => {
+        GuiState::Busy => (),
+        GuiState::PickSheets(pick) => {
+            let mut load = false;
+            let mut cancel = false;
+            egui::Window::new("PICK SHEETS").anchor(Align2::CENTER_CENTER, egui::vec2(0.0, 0.0)).show(ctx.ctx_mut(), |ui| {
+                ui.label(pick.path.display().to_string());
+                egui::Grid::new("PICK_SHEETS").show(ui, |ui| {
+                    for (label, sheet) in SHEET_FIELDS.iter().zip(pick.options.profile.sheets.fields_mut()) {
+                        ui.label(*label);
+                        let text = if sheet.is_empty() {"(none)"} else {sheet.as_str()};
+                        egui::ComboBox::from_id_source(label).selected_text(text).show_ui(ui, |ui| {
+                            ui.selectable_value(sheet, String::new(), "(none)");
+                            for name in &pick.available {
+                                ui.selectable_value(sheet, name.clone(), name);
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+                ui.checkbox(&mut pick.save, format!("Save to profile '{}'", pick.options.profile.name));
+                ui.horizontal(|ui| {
+                    load = ui.button("Load").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+            if load {
+                if pick.save {
+                    config.import.save_profile(pick.options.profile.clone());
+                    if let Err(e) = save_config(&config) {
+                        cmd.spawn(Log::new(LogType::Error, &format!("Fail to save config: {}", e)));
+                    }
+                    editor.loaded = None;
+                }
+                file_task::spawn_load(&mut cmd, pick.path.clone(), pick.options.clone());
+                *gst = GuiState::Busy;
+            } else
+            if cancel {
+                *gst = GuiState::Normal;
+            }
+        },
+        GuiState::PickChannel(pick) => {
+            let mut load = false;
+            let mut cancel = false;
+            egui::Window::new("PICK CHANNEL").anchor(Align2::CENTER_CENTER, egui::vec2(0.0, 0.0)).show(ctx.ctx_mut(), |ui| {
+                ui.label(pick.path.display().to_string());
+                ui.horizontal(|ui| {
+                    for (channel, name) in CHANNELS {
+                        ui.selectable_value(&mut pick.options.channel, Some(channel), name);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    load = ui.button("Load").clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+            if load {
+                file_task::spawn_load(&mut cmd, pick.path.clone(), pick.options.clone());
+                *gst = GuiState::Busy;
+            } else
+            if cancel {
+                *gst = GuiState::Normal;
+            }
+        },
+        GuiState::SaveData(fdialog) | GuiState::ExportShown(fdialog) => {
 			match fdialog.state() {
                 egui_file::State::Cancelled => {
-                    println!("SaveData::Canceled");
                     *gst = GuiState::Normal;
                 },
                 egui_file::State::Closed => {
@@ -79,18 +197,15 @@ pub fn show(mut cmd: Commands, mut gst: ResMut<GuiState>, mut data: ResMut<Data>
                     fdialog.show(ctx.ctx_mut());
                 },
                 egui_file::State::Selected => {
-                    println!("SaveData::Selected");
 					match fdialog.path() {
 						Some(path) => {
-							match datal::save_data(&path, &data) {
-								Err(e) => {
-									cmd.spawn(Log::new(LogType::Error, &format!("Fail to save data to file: {}", e)));
-								},
-								_ => {
-									cmd.spawn(Log::new(LogType::Info, "Data success saved."));
-								}
-							}
-							*gst = GuiState::Normal;
+							let file = if export_shown {
+								data.to_file_selected(&shown.res)
+							} else {
+								data.to_file()
+							};
+							file_task::spawn_save(&mut cmd, path, file);
+							*gst = GuiState::Busy;
 						},
 						None => {
 							fdialog.open();
@@ -105,4 +220,89 @@ pub fn show(mut cmd: Commands, mut gst: ResMut<GuiState>, mut data: ResMut<Data>
 			}
         }
     }
-}
\ No newline at end of file
+}
+
+/// Import options shown next to open file dialog.
+fn show_import_options(cmd: &mut Commands, config: &mut Config, editor: &mut ProfileEditor, ctx: &egui::Context) {
+    egui::Window::new("IMPORT").anchor(Align2::LEFT_TOP, egui::vec2(0.0, 0.0)).show(ctx, |ui| {
+        let mut mode = config.import.mode;
+        ui.horizontal(|ui| {
+            ui.label("Mode:");
+            ui.selectable_value(&mut mode, ImportMode::Strict, "Strict")
+                .on_hover_text("Reject file on first bad cell");
+            ui.selectable_value(&mut mode, ImportMode::Lenient, "Lenient")
+                .on_hover_text("Skip bad rows and show them in validation report");
+        });
+        let mut append = config.import.append;
+        ui.checkbox(&mut append, "Append to current data")
+            .on_hover_text("Load channels of several files into one dataset");
+        let mut profile = config.import.profile.clone();
+        egui::ComboBox::from_label("Profile").selected_text(&profile).show_ui(ui, |ui| {
+            for p in &config.import.profiles {
+                ui.selectable_value(&mut profile, p.name.clone(), &p.name);
+            }
+        });
+        if editor.loaded.as_ref() != Some(&profile) {
+            editor.load(&config.import.selected());
+            editor.loaded = Some(profile.clone());
+        }
+        let mut save = false;
+        ui.collapsing("Profile", |ui| {
+            ui.label("Sheet names, empty to skip channel.");
+            egui::Grid::new("IMPORT_SHEETS").show(ui, |ui| {
+                for (label, text) in SHEET_FIELDS.iter().zip(editor.sheets.iter_mut()) {
+                    ui.label(*label);
+                    ui.text_edit_singleline(text);
+                    ui.end_row();
+                }
+            });
+            ui.separator();
+            ui.label("Header names separated by comma, positional layout if not found.");
+            egui::Grid::new("IMPORT_COLUMNS").show(ui, |ui| {
+                for (label, text) in COLUMN_FIELDS.iter().zip(editor.columns.iter_mut()) {
+                    ui.label(*label);
+                    ui.text_edit_singleline(text);
+                    ui.end_row();
+                }
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Timezone:");
+                ui.selectable_value(&mut editor.timezone, SourceTimezone::Utc, "UTC");
+                ui.selectable_value(&mut editor.timezone, SourceTimezone::Local, "Local");
+                ui.selectable_value(&mut editor.timezone, SourceTimezone::Offset, "Offset");
+                if editor.timezone == SourceTimezone::Offset {
+                    ui.add(egui::DragValue::new(&mut editor.utc_offset)
+                        .clamp_range(-MAX_UTC_OFFSET..=MAX_UTC_OFFSET)
+                        .speed(15.0)
+                        .custom_formatter(|m, _| format_utc_offset(m as i32)));
+                }
+            }).response.on_hover_text("Of Excel dates and strings without offset");
+            ui.horizontal(|ui| {
+                ui.label("Numbers:");
+                ui.selectable_value(&mut editor.numeric_time, NumericTime::Unix, "Unix seconds");
+                ui.selectable_value(&mut editor.numeric_time, NumericTime::Excel, "Excel serial");
+            }).response.on_hover_text("Of datetime cells without date format");
+            ui.horizontal(|ui| {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut editor.name);
+                if ui.add_enabled(!editor.name.trim().is_empty(), egui::Button::new("Save Profile")).clicked() {
+                    save = true;
+                }
+            });
+        });
+        if save {
+            config.import.save_profile(editor.profile());
+            profile = config.import.profile.clone();
+            editor.loaded = Some(profile.clone());
+        }
+        if save || mode != config.import.mode || append != config.import.append || profile != config.import.profile {
+            config.import.mode = mode;
+            config.import.append = append;
+            config.import.profile = profile;
+            if let Err(e) = save_config(config) {
+                cmd.spawn(Log::new(LogType::Error, &format!("Fail to save config: {}", e)));
+            }
+        }
+    });
+}
@@ -1,4 +1,4 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, ecs::schedule::ShouldRun};
 use bevy_egui::{egui::{self, Align2, TextStyle, ScrollArea, RichText, Color32, Context}, EguiContext, EguiPlugin};
 use egui_file::FileDialog;
 use chrono::{DateTime};
@@ -6,6 +6,10 @@ use chrono::{DateTime};
 pub mod logger;
 pub mod menu;
 pub mod control;
+pub mod timeline;
+pub mod range_slider;
+pub mod file_task;
+pub mod report;
 
 use crate::utils::{data_loader as datal, config::Config};
 
@@ -14,7 +18,13 @@ pub enum GuiState {
     Normal,
     OpenFile(FileDialog),
     GenerateData,
-    SaveData(FileDialog)
+    SaveData(FileDialog),
+    /// Save measurements shown on map, format by extension.
+    ExportShown(FileDialog),
+    PickSheets(file_task::SheetPick),
+    PickChannel(file_task::ChannelPick),
+    /// File task is running, see `file_task`.
+    Busy
 }
 
 impl Default for GuiState {
@@ -36,21 +46,39 @@ impl Default for GuiApp {
 	}
 }
 
+/// Run criteria of windows and input hidden behind modal file task.
+pub fn not_busy(gst: Res<GuiState>) -> ShouldRun {
+    match *gst {
+        GuiState::Busy => ShouldRun::No,
+        _ => ShouldRun::Yes
+    }
+}
+
 fn gui_setup(mut cmd: Commands, config: Res<Config>) {
     cmd.insert_resource(GuiState::default());
     cmd.insert_resource(control::Control::new(&config.default_deltas));
+    cmd.insert_resource(config.layers);
+    cmd.insert_resource(timeline::TimePicker::default());
+    cmd.insert_resource(control::Playback::new(&config.playback));
+    cmd.insert_resource(report::ValidationReport::default());
 }
 
 impl Plugin for GuiApp {
     fn build(&self, app: &mut App) {
         app.add_startup_system(gui_setup);
-        app.add_system(logger::show);
+        app.add_system(logger::show.with_run_criteria(not_busy));
         app.add_system(logger::clear);
         app.add_system(menu::show);
-        app.add_system(control::show);
+        app.add_system(file_task::show);
+        app.add_system(file_task::finish);
+        app.add_system(report::show.with_run_criteria(not_busy));
+        app.add_system(control::show.with_run_criteria(not_busy));
         app.add_system(control::update_ranges);
+        app.add_system(timeline::show_picker.with_run_criteria(not_busy));
+        app.add_system(control::playback);
         app.add_event::<logger::EventClear>();
         app.add_event::<control::EventControlDataChanged>();
+        app.add_event::<control::EventLayersChanged>();
     }
 
     fn name(&self) -> &str {
@@ -0,0 +1,60 @@
+use std::ops::RangeInclusive;
+use bevy_egui::egui::{self, Sense, Response, Pos2, Rect, Stroke};
+
+/// Slider with two handles selecting [`lo`, `hi`] inside `range`.
+/// Pointer moves the handle nearest to it when drag starts.
+pub fn range_slider(ui: &mut egui::Ui, lo: &mut f64, hi: &mut f64, range: RangeInclusive<f64>, vertical: bool, length: f32) -> Response {
+	let thickness = ui.spacing().interact_size.y;
+	let size = if vertical {egui::vec2(thickness, length)} else {egui::vec2(length, thickness)};
+	let (rect, mut resp) = ui.allocate_exact_size(size, Sense::click_and_drag());
+	let (min, max) = (*range.start(), *range.end());
+	// Same inset as handle radius of egui Slider.
+	let radius = thickness / 2.5;
+	let (p0, p1) = if vertical {(rect.bottom() - radius, rect.top() + radius)} else {(rect.left() + radius, rect.right() - radius)};
+	let to_pos = |v: f64| -> f32 {
+		if max > min {p0 + ((v - min) / (max - min)).clamp(0.0, 1.0) as f32 * (p1 - p0)} else {p0}
+	};
+	let to_val = |p: f32| -> f64 {
+		if p1 != p0 {min + ((p - p0) / (p1 - p0)).clamp(0.0, 1.0) as f64 * (max - min)} else {min}
+	};
+
+	if let Some(pointer) = resp.interact_pointer_pos() {
+		let p = if vertical {pointer.y} else {pointer.x};
+		let stored = ui.data().get_temp::<bool>(resp.id);
+		let is_hi = match stored {
+			Some(h) if !resp.drag_started() => h,
+			_ => {
+				let h = (p - to_pos(*hi)).abs() < (p - to_pos(*lo)).abs();
+				ui.data().insert_temp(resp.id, h);
+				h
+			}
+		};
+		let v = to_val(p);
+		let (old_lo, old_hi) = (*lo, *hi);
+		if is_hi {
+			*hi = v.max(*lo);
+		} else {
+			*lo = v.min(*hi);
+		}
+		if *lo != old_lo || *hi != old_hi {
+			resp.mark_changed();
+		}
+	}
+
+	let visuals = ui.style().interact(&resp);
+	let center = rect.center();
+	let (a, b) = (to_pos(*lo), to_pos(*hi));
+	let point = |p: f32| if vertical {Pos2::new(center.x, p)} else {Pos2::new(p, center.y)};
+	let rail = if vertical {
+		Rect::from_min_max(Pos2::new(center.x - 2.0, rect.top()), Pos2::new(center.x + 2.0, rect.bottom()))
+	} else {
+		Rect::from_min_max(Pos2::new(rect.left(), center.y - 2.0), Pos2::new(rect.right(), center.y + 2.0))
+	};
+	let painter = ui.painter();
+	painter.rect_filled(rail, 2.0, ui.visuals().widgets.inactive.bg_fill);
+	painter.line_segment([point(a), point(b)], Stroke::new(4.0, ui.visuals().selection.bg_fill));
+	for p in [a, b] {
+		painter.circle(point(p), radius, visuals.bg_fill, visuals.fg_stroke);
+	}
+	resp
+}
@@ -0,0 +1,58 @@
+use std::path::PathBuf;
+use bevy::prelude::*;
+use bevy_egui::{EguiContext, egui::{self, Align2, ScrollArea, TextStyle, Color32, RichText}};
+
+use crate::utils::data_error::DataError;
+
+const REPORT_LOCATION_WIDTH: f32 = 100.0;
+
+/// Rows skipped by lenient import of last file.
+#[derive(Resource)]
+pub struct ValidationReport {
+	pub path: PathBuf,
+	pub problems: Vec<DataError>,
+	pub open: bool
+}
+
+impl Default for ValidationReport {
+	fn default() -> Self {
+		Self {
+			path: PathBuf::new(),
+			problems: Vec::new(),
+			open: false
+		}
+	}
+}
+
+pub fn show(mut ctx: ResMut<EguiContext>, mut report: ResMut<ValidationReport>) {
+	if !report.open {
+		return;
+	}
+	let mut open = true;
+	egui::Window::new("VALIDATION REPORT")
+	.anchor(Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+	.default_size(egui::vec2(500.0, 300.0))
+	.resizable(true)
+	.open(&mut open)
+	.show(ctx.ctx_mut(), |ui| {
+		ui.label(format!("{}: {} rows skipped", report.path.display(), report.problems.len()));
+		ui.separator();
+		let row_height = ui.text_style_height(&TextStyle::Body);
+		ScrollArea::vertical().auto_shrink([false; 2]).show_rows(
+			ui,
+			row_height,
+			report.problems.len(),
+			|ui, rows| {
+				for p in &report.problems[rows] {
+					ui.horizontal(|ui| {
+						ui.add_sized([REPORT_LOCATION_WIDTH, row_height], egui::Label::new(RichText::new(p.location().unwrap_or_default()).monospace().color(Color32::GOLD)));
+						ui.label(p.reason());
+					});
+				}
+			}
+		);
+	});
+	if !open {
+		report.open = false;
+	}
+}
@@ -0,0 +1,228 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContext, egui::{self, Align2, DragValue, Grid}};
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::config::Config;
+use super::control::{Control, EventControlDataChanged};
+
+pub const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const WEEKDAYS: [&str; 7] = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeDisplay {
+	Utc,
+	Local
+}
+
+impl Default for TimeDisplay {
+	fn default() -> Self {
+		Self::Utc
+	}
+}
+
+impl TimeDisplay {
+	pub fn naive_at(&self, ts: i64) -> Option<NaiveDateTime> {
+		match self {
+			TimeDisplay::Utc => Utc.timestamp_opt(ts, 0).single().map(|dt| dt.naive_local()),
+			TimeDisplay::Local => Local.timestamp_opt(ts, 0).single().map(|dt| dt.naive_local())
+		}
+	}
+
+	pub fn timestamp_of(&self, dt: &NaiveDateTime) -> Option<i64> {
+		match self {
+			TimeDisplay::Utc => Some(Utc.from_utc_datetime(dt).timestamp()),
+			TimeDisplay::Local => Local.from_local_datetime(dt).earliest().map(|dt| dt.timestamp())
+		}
+	}
+
+	pub fn format(&self, ts: i64) -> String {
+		match self.naive_at(ts) {
+			Some(dt) => match self {
+				TimeDisplay::Utc => format!("{} UTC", dt.format(DATETIME_FORMAT)),
+				TimeDisplay::Local => dt.format(DATETIME_FORMAT).to_string()
+			},
+			None => ts.to_string()
+		}
+	}
+}
+
+/// Date and time being picked, picker window is shown while set.
+#[derive(Resource, Default)]
+pub struct TimePicker {
+	pub value: Option<NaiveDateTime>
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+	let next = if month == 12 {NaiveDate::from_ymd_opt(year + 1, 1, 1)} else {NaiveDate::from_ymd_opt(year, month + 1, 1)};
+	match (next, NaiveDate::from_ymd_opt(year, month, 1)) {
+		(Some(next), Some(first)) => (next - first).num_days() as u32,
+		_ => 31
+	}
+}
+
+fn with_date(dt: &NaiveDateTime, year: i32, month: u32, day: u32) -> NaiveDateTime {
+	let day = day.min(days_in_month(year, month));
+	match NaiveDate::from_ymd_opt(year, month, day) {
+		Some(d) => d.and_time(dt.time()),
+		None => *dt
+	}
+}
+
+pub fn show_picker(
+	mut ctx: ResMut<EguiContext>,
+	mut picker: ResMut<TimePicker>,
+	mut ctld: ResMut<Control>,
+	config: Res<Config>,
+	mut evw: EventWriter<EventControlDataChanged>
+) {
+	let mut dt = match picker.value {
+		Some(dt) => dt,
+		None => return
+	};
+	let mut close = false;
+	egui::Window::new("GO TO TIME").anchor(Align2::CENTER_TOP, egui::vec2(0.0, 80.0)).resizable(false).show(ctx.ctx_mut(), |ui| {
+		ui.horizontal(|ui| {
+			if ui.button("◀").clicked() {
+				dt = if dt.month() == 1 {with_date(&dt, dt.year() - 1, 12, dt.day())} else {with_date(&dt, dt.year(), dt.month() - 1, dt.day())};
+			}
+			let mut year = dt.year();
+			if ui.add(DragValue::new(&mut year)).changed() {
+				dt = with_date(&dt, year, dt.month(), dt.day());
+			}
+			ui.label(dt.format("%B").to_string());
+			if ui.button("▶").clicked() {
+				dt = if dt.month() == 12 {with_date(&dt, dt.year() + 1, 1, dt.day())} else {with_date(&dt, dt.year(), dt.month() + 1, dt.day())};
+			}
+		});
+		let first = with_date(&dt, dt.year(), dt.month(), 1);
+		let offset = first.weekday().num_days_from_monday();
+		Grid::new("PICKER_DAYS").show(ui, |ui| {
+			for wd in WEEKDAYS {
+				ui.label(wd);
+			}
+			ui.end_row();
+			for _ in 0..offset {
+				ui.label("");
+			}
+			for day in 1..=days_in_month(dt.year(), dt.month()) {
+				if ui.selectable_label(day == dt.day(), day.to_string()).clicked() {
+					dt = with_date(&dt, dt.year(), dt.month(), day);
+				}
+				if (day + offset) % 7 == 0 {
+					ui.end_row();
+				}
+			}
+		});
+		ui.horizontal(|ui| {
+			let (mut h, mut m, mut s) = (dt.hour(), dt.minute(), dt.second());
+			ui.add(DragValue::new(&mut h).clamp_range(0..=23));
+			ui.label(":");
+			ui.add(DragValue::new(&mut m).clamp_range(0..=59));
+			ui.label(":");
+			ui.add(DragValue::new(&mut s).clamp_range(0..=59));
+			if let Some(t) = dt.date().and_hms_opt(h, m, s) {
+				dt = t;
+			}
+		});
+		ui.horizontal(|ui| {
+			if ui.button("Go").clicked() {
+				if let Some(ts) = config.time_display.timestamp_of(&dt) {
+					ctld.timestamp = ts.clamp(ctld.ranges.timestamp_min, ctld.ranges.timestamp_max);
+					evw.send(EventControlDataChanged);
+				}
+				close = true;
+			}
+			if ui.button("Cancel").clicked() {
+				close = true;
+			}
+		});
+	});
+	picker.value = if close {None} else {Some(dt)};
+}
+
+/// Nearest record timestamp before (`forward` false) or after current one.
+pub fn step_record(timestamps: &[i64], current: i64, forward: bool) -> Option<i64> {
+	if forward {
+		let i = timestamps.partition_point(|t| *t <= current);
+		timestamps.get(i).copied()
+	} else {
+		let i = timestamps.partition_point(|t| *t < current);
+		if i > 0 {Some(timestamps[i - 1])} else {None}
+	}
+}
+
+/// Whole days, hours and minutes of `secs`, for delta labels.
+pub fn format_duration(secs: i64) -> String {
+	let d = Duration::seconds(secs);
+	if d.num_days() > 0 {
+		format!("{}d {}h", d.num_days(), d.num_hours() % 24)
+	} else if d.num_hours() > 0 {
+		format!("{}h {}m", d.num_hours(), d.num_minutes() % 60)
+	} else {
+		format!("{}m {}s", d.num_minutes(), secs % 60)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const TIMESTAMPS: [i64; 4] = [100, 200, 200, 300];
+
+	#[test]
+	fn step_forward() {
+		assert_eq!(step_record(&TIMESTAMPS, 50, true), Some(100));
+		assert_eq!(step_record(&TIMESTAMPS, 100, true), Some(200));
+		assert_eq!(step_record(&TIMESTAMPS, 200, true), Some(300));
+		assert_eq!(step_record(&TIMESTAMPS, 300, true), None);
+		assert_eq!(step_record(&TIMESTAMPS, 400, true), None);
+	}
+
+	#[test]
+	fn step_backward() {
+		assert_eq!(step_record(&TIMESTAMPS, 400, false), Some(300));
+		assert_eq!(step_record(&TIMESTAMPS, 300, false), Some(200));
+		assert_eq!(step_record(&TIMESTAMPS, 200, false), Some(100));
+		assert_eq!(step_record(&TIMESTAMPS, 100, false), None);
+		assert_eq!(step_record(&TIMESTAMPS, 50, false), None);
+		assert_eq!(step_record(&[], 100, false), None);
+	}
+
+	#[test]
+	fn durations() {
+		assert_eq!(format_duration(0), "0m 0s");
+		assert_eq!(format_duration(59), "0m 59s");
+		assert_eq!(format_duration(3_600 + 120 + 5), "1h 2m");
+		assert_eq!(format_duration(86_400 * 2 + 3_600 * 5), "2d 5h");
+	}
+
+	#[test]
+	fn utc_display() {
+		let ts = 1_672_574_400;
+		assert_eq!(TimeDisplay::Utc.format(ts), "2023-01-01 12:00:00 UTC");
+		let dt = TimeDisplay::Utc.naive_at(ts).unwrap();
+		assert_eq!((dt.hour(), dt.minute()), (12, 0));
+		assert_eq!(TimeDisplay::Utc.timestamp_of(&dt), Some(ts));
+	}
+
+	#[test]
+	fn local_display() {
+		let ts = 1_672_574_400;
+		let dt = TimeDisplay::Local.naive_at(ts).unwrap();
+		assert_eq!(dt, Local.timestamp_opt(ts, 0).unwrap().naive_local());
+		assert_eq!(TimeDisplay::Local.timestamp_of(&dt), Some(ts));
+		assert_eq!(TimeDisplay::Local.format(ts), dt.format(DATETIME_FORMAT).to_string());
+		assert!(!TimeDisplay::Local.format(ts).ends_with("UTC"));
+	}
+
+	#[test]
+	fn month_lengths() {
+		assert_eq!(days_in_month(2024, 2), 29);
+		assert_eq!(days_in_month(2023, 2), 28);
+		assert_eq!(days_in_month(2023, 12), 31);
+		let dt = NaiveDate::from_ymd_opt(2023, 1, 31).unwrap().and_hms_opt(10, 0, 0).unwrap();
+		assert_eq!(with_date(&dt, 2023, 2, 31).day(), 28);
+	}
+}
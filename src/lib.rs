@@ -0,0 +1,5 @@
+pub mod utils;
+pub mod frames;
+pub mod repr_2d;
+pub mod repr_3d;
+pub mod data_gen;
@@ -3,14 +3,7 @@ use std::{io::Error};
 use bevy::prelude::*;
 use bevy_egui::EguiPlugin;
 
-pub mod utils;
-use utils::data_loader as datal;
-
-mod frames;
-use frames::GuiApp;
-
-mod repr_2d;
-mod repr_3d;
+use visio::{utils::{self, data_loader as datal}, frames::GuiApp, repr_2d};
 
 fn setup() {
 
@@ -21,8 +14,10 @@ fn main() -> Result<(), Error> {
     let mut app = App::new();
     app.insert_resource(config);
     app.insert_resource(datal::Data::default());
+    app.insert_resource(utils::projection::Projection::default());
     app.add_plugins(DefaultPlugins);
     app.add_startup_system(setup);
+    app.add_system_to_stage(CoreStage::PreUpdate, utils::projection::update_projection);
     app.add_plugin(EguiPlugin);
     app.add_plugin(GuiApp::default());
     app.add_plugin(repr_2d::Repr2D::default());
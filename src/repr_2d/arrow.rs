@@ -0,0 +1,80 @@
+use bevy::{prelude::*, render::{mesh::Indices, render_resource::PrimitiveTopology}};
+use serde::{Deserialize, Serialize};
+
+const HEAD_WIDTH_RATIO: f32 = 3.0;
+const HEAD_LENGTH_RATIO: f32 = 3.0;
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ArrowScale {
+	Linear,
+	Log
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ArrowConfig {
+	pub scale: ArrowScale,
+	/// Arrow length in pixels per unit of speed (or of ln(1 + speed) for log scale).
+	pub length: f64,
+	pub width: f64
+}
+
+impl Default for ArrowConfig {
+	fn default() -> Self {
+		Self {
+			scale: ArrowScale::Linear,
+			length: 20.0,
+			width: 2.0
+		}
+	}
+}
+
+impl ArrowConfig {
+	pub fn length(&self, speed: f64) -> f32 {
+		let speed = speed.abs();
+		let len = match self.scale {
+			ArrowScale::Linear => speed * self.length,
+			ArrowScale::Log => (1.0 + speed).ln() * self.length
+		};
+		len as f32
+	}
+
+	pub fn mesh(&self, speed: f64) -> Mesh {
+		arrow_mesh(self.length(speed), self.width as f32)
+	}
+}
+
+/// Rotation for flow direction given in degrees clockwise from north.
+pub fn rotation(dir: f64) -> Quat {
+	Quat::from_rotation_z(-(dir as f32).to_radians())
+}
+
+/// Arrow with base at origin pointing along +Y.
+pub fn arrow_mesh(length: f32, width: f32) -> Mesh {
+	let hw = width / 2.0;
+	let hh = hw * HEAD_WIDTH_RATIO;
+	let head = (width * HEAD_LENGTH_RATIO).min(length);
+	let shaft = length - head;
+
+	let positions = vec![
+		[-hw, 0.0, 0.0],
+		[hw, 0.0, 0.0],
+		[hw, shaft, 0.0],
+		[-hw, shaft, 0.0],
+		[-hh, shaft, 0.0],
+		[hh, shaft, 0.0],
+		[0.0, length, 0.0]
+	];
+	let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+	let uvs: Vec<[f32; 2]> = positions.iter().map(|p| {
+		[0.5 + p[0] / (2.0 * hh), 1.0 - if length > 0.0 {p[1] / length} else {0.0}]
+	}).collect();
+	let indices = vec![0, 1, 2, 0, 2, 3, 4, 5, 6];
+
+	let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+	mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+	mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+	mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+	mesh.set_indices(Some(Indices::U32(indices)));
+	mesh
+}
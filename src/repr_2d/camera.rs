@@ -0,0 +1,153 @@
+use bevy::{prelude::*, input::mouse::{MouseMotion, MouseWheel, MouseScrollUnit}};
+use bevy_egui::EguiContext;
+
+use crate::utils::{data_loader::Data, projection::Projection};
+
+const ZOOM_STEP: f32 = 1.1;
+const ZOOM_PIXELS_PER_LINE: f32 = 100.0;
+const SCALE_MIN: f32 = 0.001;
+const SCALE_MAX: f32 = 100_000.0;
+const FIT_MARGIN: f32 = 1.1;
+
+pub struct EventFitToData;
+
+/// Entity sized in screen pixels, placed at `pos` world position shifted by `offset` pixels.
+#[derive(Component)]
+pub struct ScreenScaled {
+	pub pos: Vec2,
+	pub offset: Vec2
+}
+
+impl ScreenScaled {
+	pub fn at(pos: Vec2) -> Self {
+		Self {
+			pos: pos,
+			offset: Vec2::ZERO
+		}
+	}
+}
+
+/// Bounding box of border and all measurements in world coordinates.
+pub fn data_bounds(data: &Data, proj: &Projection) -> Option<(Vec2, Vec2)> {
+	let points = data.points();
+	let mut min = Vec2::splat(f32::MAX);
+	let mut max = Vec2::splat(f32::MIN);
+	for p in points {
+		let v = proj.to_world(&p);
+		min = min.min(v);
+		max = max.max(v);
+	}
+	if min.x > max.x {
+		return None;
+	}
+	Some((min, max))
+}
+
+/// World position under cursor.
+pub fn cursor_world(window: &Window, tf: &Transform, ortho: &OrthographicProjection) -> Option<Vec2> {
+	let cursor = window.cursor_position()?;
+	let from_center = cursor - Vec2::new(window.width(), window.height()) / 2.0;
+	Some(tf.translation.truncate() + from_center * ortho.scale)
+}
+
+pub fn camera_control(
+	windows: Res<Windows>,
+	mut ctx: ResMut<EguiContext>,
+	buttons: Res<Input<MouseButton>>,
+	mut motion: EventReader<MouseMotion>,
+	mut wheel: EventReader<MouseWheel>,
+	mut cams: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>
+) {
+	let egui_ctx = ctx.ctx_mut();
+	if egui_ctx.wants_pointer_input() || egui_ctx.is_pointer_over_area() {
+		motion.clear();
+		wheel.clear();
+		return;
+	}
+	let window = match windows.get_primary() {
+		Some(w) => w,
+		None => return
+	};
+	let (mut tf, mut ortho) = match cams.get_single_mut() {
+		Ok(cam) => cam,
+		Err(_) => return
+	};
+
+	if buttons.pressed(MouseButton::Left) {
+		for m in motion.iter() {
+			tf.translation.x -= m.delta.x * ortho.scale;
+			tf.translation.y += m.delta.y * ortho.scale;
+		}
+	} else {
+		motion.clear();
+	}
+
+	let mut lines = 0.0;
+	for w in wheel.iter() {
+		lines += match w.unit {
+			MouseScrollUnit::Line => w.y,
+			MouseScrollUnit::Pixel => w.y / ZOOM_PIXELS_PER_LINE
+		};
+	}
+	if lines == 0.0 {
+		return;
+	}
+	let new_scale = (ortho.scale * ZOOM_STEP.powf(-lines)).clamp(SCALE_MIN, SCALE_MAX);
+	// Keep world point under cursor in place.
+	if let Some(world) = cursor_world(window, &tf, &ortho) {
+		let pos = tf.translation.truncate() + (world - tf.translation.truncate()) * (1.0 - new_scale / ortho.scale);
+		tf.translation.x = pos.x;
+		tf.translation.y = pos.y;
+	}
+	ortho.scale = new_scale;
+}
+
+pub fn fit_to_data(
+	evr: EventReader<EventFitToData>,
+	data: Res<Data>,
+	proj: Res<Projection>,
+	windows: Res<Windows>,
+	mut cams: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>
+) {
+	if evr.is_empty() && !proj.is_changed() {
+		return;
+	}
+	evr.clear();
+	let window = match windows.get_primary() {
+		Some(w) => w,
+		None => return
+	};
+	let (mut tf, mut ortho) = match cams.get_single_mut() {
+		Ok(cam) => cam,
+		Err(_) => return
+	};
+	let (min, max) = match data_bounds(&data, &proj) {
+		Some(b) => b,
+		None => return
+	};
+	let center = (min + max) / 2.0;
+	let size = max - min;
+	tf.translation.x = center.x;
+	tf.translation.y = center.y;
+	let scale = f32::max(size.x / window.width(), size.y / window.height()) * FIT_MARGIN;
+	ortho.scale = if scale > 0.0 {scale.clamp(SCALE_MIN, SCALE_MAX)} else {1.0};
+}
+
+pub fn update_screen_scaled(
+	cams: Query<(&OrthographicProjection, ChangeTrackers<OrthographicProjection>), With<Camera2d>>,
+	mut items: Query<(&ScreenScaled, &mut Transform, ChangeTrackers<ScreenScaled>)>
+) {
+	let (ortho, ortho_tracker) = match cams.get_single() {
+		Ok(cam) => cam,
+		Err(_) => return
+	};
+	for (ss, mut tf, tracker) in &mut items {
+		if !ortho_tracker.is_changed() && !tracker.is_changed() {
+			continue;
+		}
+		let pos = ss.pos + ss.offset * ortho.scale;
+		tf.translation.x = pos.x;
+		tf.translation.y = pos.y;
+		tf.scale = Vec3::new(ortho.scale, ortho.scale, 1.0);
+	}
+}
@@ -0,0 +1,107 @@
+use bevy::prelude::Color;
+use serde::{Deserialize, Serialize};
+
+// 0.318 is a colormap sample, not an approximation of 1/π.
+#[allow(clippy::approx_constant)]
+const VIRIDIS: [(f32, f32, f32); 11] = [
+	(0.267, 0.005, 0.329),
+	(0.283, 0.141, 0.458),
+	(0.254, 0.265, 0.530),
+	(0.207, 0.372, 0.553),
+	(0.164, 0.471, 0.558),
+	(0.128, 0.567, 0.551),
+	(0.135, 0.659, 0.518),
+	(0.267, 0.749, 0.441),
+	(0.478, 0.821, 0.318),
+	(0.741, 0.873, 0.150),
+	(0.993, 0.906, 0.144)
+];
+
+const DIVERGING: [(f32, f32, f32); 3] = [
+	(0.230, 0.299, 0.754),
+	(0.865, 0.865, 0.865),
+	(0.706, 0.016, 0.150)
+];
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Colormap {
+	Viridis,
+	Jet,
+	Diverging
+}
+
+impl Colormap {
+	/// Color at position `t` in [0, 1], out of range values are clamped.
+	pub fn color(&self, t: f64) -> Color {
+		let t = if t.is_nan() {0.0} else {t.clamp(0.0, 1.0) as f32};
+		match self {
+			Colormap::Viridis => lerp_stops(&VIRIDIS, t),
+			Colormap::Jet => Color::rgb(
+				(1.5 - (4.0*t - 3.0).abs()).clamp(0.0, 1.0),
+				(1.5 - (4.0*t - 2.0).abs()).clamp(0.0, 1.0),
+				(1.5 - (4.0*t - 1.0).abs()).clamp(0.0, 1.0)
+			),
+			Colormap::Diverging => lerp_stops(&DIVERGING, t)
+		}
+	}
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ColormapConfig {
+	pub map: Colormap,
+	/// Take range from min/max of currently shown values instead of `min`/`max`.
+	pub auto_range: bool,
+	pub min: f64,
+	pub max: f64
+}
+
+impl Default for ColormapConfig {
+	fn default() -> Self {
+		Self {
+			map: Colormap::Viridis,
+			auto_range: true,
+			min: 0.0,
+			max: 30.0
+		}
+	}
+}
+
+impl ColormapConfig {
+	pub fn range(&self, vals: impl Iterator<Item = f64>) -> (f64, f64) {
+		if !self.auto_range {
+			return (self.min, self.max);
+		}
+		let mut min = f64::MAX;
+		let mut max = f64::MIN;
+		for v in vals {
+			if v < min {min = v}
+			if v > max {max = v}
+		}
+		if min > max {
+			return (self.min, self.max);
+		}
+		(min, max)
+	}
+
+	pub fn color(&self, val: f64, range: (f64, f64)) -> Color {
+		let span = range.1 - range.0;
+		if span.abs() < f64::EPSILON {
+			return self.map.color(0.5);
+		}
+		self.map.color((val - range.0) / span)
+	}
+}
+
+fn lerp_stops(stops: &[(f32, f32, f32)], t: f32) -> Color {
+	let pos = t * (stops.len() - 1) as f32;
+	let i = (pos.floor() as usize).min(stops.len() - 2);
+	let f = pos - i as f32;
+	let a = stops[i];
+	let b = stops[i+1];
+	Color::rgb(
+		a.0 + (b.0 - a.0)*f,
+		a.1 + (b.1 - a.1)*f,
+		a.2 + (b.2 - a.2)*f
+	)
+}
@@ -1,6 +1,31 @@
 use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
 
-use crate::frames::control::{Control, EventControlDataChanged};
+use crate::frames::{not_busy, control::{Control, EventControlDataChanged, EventLayersChanged, LayerVisibility}};
+use crate::utils::{data_loader as datal, config::Config, projection::Projection};
+use datal::Data;
+
+pub mod colormap;
+pub mod arrow;
+pub mod spectrum;
+pub mod outline;
+pub mod camera;
+pub mod probe;
+
+use camera::ScreenScaled;
+
+const POINT_RADIUS: f32 = 5.0;
+const FLAG_RADIUS: f32 = 8.0;
+const BG_Z: f32 = 0.0;
+const GRID_Z: f32 = 0.3;
+const GRID_LINES: f64 = 10.0;
+const GRID_STEPS: [f64; 13] = [0.0001, 0.0002, 0.0005, 0.001, 0.002, 0.005, 0.01, 0.02, 0.05, 0.1, 0.2, 0.5, 1.0];
+const BORDER_Z: f32 = 0.5;
+const FLAG_Z: f32 = 0.9;
+const POINT_Z: f32 = 1.0;
+const LABEL_Z: f32 = 2.0;
+const LABEL_FONT: &str = "fonts/Hack-Regular.ttf";
+const LABEL_FONT_SIZE: f32 = 12.0;
+const LABEL_OFFSET: f32 = 12.0;
 
 pub struct Repr2D {
 
@@ -17,7 +42,18 @@ impl Default for Repr2D {
 impl Plugin for Repr2D {
 	fn build(&self, app: &mut App) {
 		app.add_startup_system(setup);
+		app.add_system(mark_shown_stale.before(redraw));
 		app.add_system(redraw);
+		app.add_system(update_background);
+		app.add_system(update_border);
+		app.add_system(update_grid);
+		app.add_system(apply_layers);
+		app.add_system(camera::camera_control.with_run_criteria(not_busy));
+		app.add_system(camera::fit_to_data);
+		app.add_system_to_stage(CoreStage::PostUpdate, camera::update_screen_scaled.before(bevy::transform::TransformSystem::TransformPropagate));
+		app.add_system(probe::show.with_run_criteria(not_busy));
+		app.add_event::<camera::EventFitToData>();
+		app.init_resource::<Shown>();
 	}
 
 	fn name(&self) -> &str {
@@ -29,23 +65,297 @@ impl Plugin for Repr2D {
 	}
 }
 
+/// Marker for every entity spawned from query result, despawned on each redraw.
+#[derive(Component)]
+struct Item2D;
+
+/// Query result currently drawn.
+#[derive(Resource, Default)]
+pub struct Shown {
+	pub res: datal::QueryResult,
+	/// Data changed since `res` was queried, indices may be invalid.
+	pub stale: bool
+}
+
+fn mark_shown_stale(data: Res<Data>, mut shown: ResMut<Shown>) {
+	if data.is_changed() {
+		shown.stale = true;
+	}
+}
+
+#[derive(Component, Clone, Copy, PartialEq)]
+enum Layer {
+	Background,
+	Border,
+	Photo,
+	Temp,
+	Flow,
+	Grid
+}
+
+impl Layer {
+	fn is_visible(&self, lv: &LayerVisibility) -> bool {
+		match self {
+			Layer::Background => lv.background,
+			Layer::Border => lv.border,
+			Layer::Photo => lv.photo,
+			Layer::Temp => lv.temp,
+			Layer::Flow => lv.flow,
+			Layer::Grid => lv.grid
+		}
+	}
+}
+
+/// Text label, hidden with labels toggle in addition to its layer.
+#[derive(Component)]
+struct Label;
+
+#[derive(Component)]
+struct BgImage;
+
+#[derive(Component)]
+struct BorderOutline;
+
+#[derive(Component)]
+struct GridLine;
+
+#[derive(Resource)]
+struct Palette {
+	point_mesh: Handle<Mesh>,
+	flow_material: Handle<ColorMaterial>,
+	border_material: Handle<ColorMaterial>,
+	grid_material: Handle<ColorMaterial>,
+	flag_mesh: Handle<Mesh>,
+	flag_material: Handle<ColorMaterial>,
+	font: Handle<Font>
+}
+
 fn setup(mut cmd: Commands, asset_srv: Res<AssetServer>, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<ColorMaterial>>) {
 	cmd.spawn(Camera2dBundle::default());
-    cmd.spawn(SpriteBundle {
-        texture: asset_srv.load("test1.png"),
-        ..default()
-    });
-	// Circle
-	cmd.spawn(MaterialMesh2dBundle {
-		mesh: meshes.add(shape::Circle::new(50.).into()).into(),
-		material: materials.add(ColorMaterial::from(Color::PURPLE)),
-		transform: Transform::from_translation(Vec3::new(-100., 0., 0.)),
-		..default()
+	cmd.insert_resource(Palette {
+		point_mesh: meshes.add(shape::Circle::new(POINT_RADIUS).into()),
+		flow_material: materials.add(ColorMaterial::from(Color::CYAN)),
+		border_material: materials.add(ColorMaterial::from(Color::WHITE)),
+		grid_material: materials.add(ColorMaterial::from(Color::rgba(1.0, 1.0, 1.0, 0.3))),
+		flag_mesh: meshes.add(shape::Circle::new(FLAG_RADIUS).into()),
+		flag_material: materials.add(ColorMaterial::from(Color::RED)),
+		font: asset_srv.load(LABEL_FONT)
 	});
 }
 
-fn redraw(mut cmd: Commands, ctld: Res<Control>, evr: EventReader<EventControlDataChanged>) {
-	if !evr.is_empty() {
-		println!("CONTROL DATA CHANGED>");
+fn update_background(mut cmd: Commands, data: Res<Data>, proj: Res<Projection>, asset_srv: Res<AssetServer>, bgs: Query<Entity, With<BgImage>>) {
+	if !data.is_changed() && !proj.is_changed() {
+		return;
+	}
+	for e in &bgs {
+		cmd.entity(e).despawn();
+	}
+	let image = match &data.bg.image {
+		Some(image) => image,
+		None => return
+	};
+	let pos = match &image.anchor {
+		Some(p) => proj.to_world(p),
+		// Projection origin.
+		None => Vec2::ZERO
+	};
+	let scale = image.scale as f32;
+	cmd.spawn((
+		SpriteBundle {
+			texture: asset_srv.load(image.image_path.as_str()),
+			transform: Transform {
+				translation: pos.extend(BG_Z),
+				rotation: Quat::from_rotation_z(-(image.rotate as f32).to_radians()),
+				scale: Vec3::new(scale, scale, 1.0)
+			},
+			..default()
+		},
+		Layer::Background,
+		BgImage
+	));
+}
+
+fn update_border(mut cmd: Commands, data: Res<Data>, proj: Res<Projection>, pal: Res<Palette>, mut meshes: ResMut<Assets<Mesh>>, outlines: Query<Entity, With<BorderOutline>>) {
+	if !data.is_changed() && !proj.is_changed() {
+		return;
+	}
+	for e in &outlines {
+		cmd.entity(e).despawn();
 	}
-}
\ No newline at end of file
+	if data.bg.border.len() < 2 {
+		return;
+	}
+	let points: Vec<Vec2> = data.bg.border.iter().map(|p| proj.to_world(p)).collect();
+	cmd.spawn((
+		MaterialMesh2dBundle {
+			mesh: meshes.add(outline::polyline_mesh(&points, true)).into(),
+			material: pal.border_material.clone(),
+			transform: Transform::from_translation(Vec3::new(0.0, 0.0, BORDER_Z)),
+			..default()
+		},
+		Layer::Border,
+		BorderOutline
+	));
+}
+
+/// Graticule over data bounds, step chosen to give about `GRID_LINES` lines.
+fn update_grid(mut cmd: Commands, data: Res<Data>, proj: Res<Projection>, pal: Res<Palette>, mut meshes: ResMut<Assets<Mesh>>, lines: Query<Entity, With<GridLine>>) {
+	if !data.is_changed() && !proj.is_changed() {
+		return;
+	}
+	for e in &lines {
+		cmd.entity(e).despawn();
+	}
+	let (min, max) = match camera::data_bounds(&data, &proj) {
+		Some(b) => b,
+		None => return
+	};
+	let sw = proj.to_point(min);
+	let ne = proj.to_point(max);
+	let span = f64::max(ne.latitude - sw.latitude, ne.longitude - sw.longitude);
+	let step = *GRID_STEPS.iter().find(|s| span / **s <= GRID_LINES).unwrap_or(&GRID_STEPS[GRID_STEPS.len() - 1]);
+	let lat0 = (sw.latitude / step).floor() * step;
+	let lat1 = (ne.latitude / step).ceil() * step;
+	let lon0 = (sw.longitude / step).floor() * step;
+	let lon1 = (ne.longitude / step).ceil() * step;
+	let mut polylines = Vec::new();
+	let mut lat = lat0;
+	while lat <= lat1 + step / 2.0 {
+		polylines.push(vec![datal::Point {latitude: lat, longitude: lon0, deep: 0.0}, datal::Point {latitude: lat, longitude: lon1, deep: 0.0}]);
+		lat += step;
+	}
+	let mut lon = lon0;
+	while lon <= lon1 + step / 2.0 {
+		polylines.push(vec![datal::Point {latitude: lat0, longitude: lon, deep: 0.0}, datal::Point {latitude: lat1, longitude: lon, deep: 0.0}]);
+		lon += step;
+	}
+	for pl in polylines {
+		let points: Vec<Vec2> = pl.iter().map(|p| proj.to_world(p)).collect();
+		cmd.spawn((
+			MaterialMesh2dBundle {
+				mesh: meshes.add(outline::polyline_mesh(&points, false)).into(),
+				material: pal.grid_material.clone(),
+				transform: Transform::from_translation(Vec3::new(0.0, 0.0, GRID_Z)),
+				..default()
+			},
+			Layer::Grid,
+			GridLine
+		));
+	}
+}
+
+fn apply_layers(lv: Res<LayerVisibility>, evr: EventReader<EventLayersChanged>, mut items: Query<(&Layer, Option<&Label>, &mut Visibility, ChangeTrackers<Layer>)>) {
+	let all = !evr.is_empty();
+	evr.clear();
+	for (layer, label, mut vis, tracker) in &mut items {
+		if !all && !tracker.is_added() {
+			continue;
+		}
+		vis.is_visible = layer.is_visible(&lv) && (label.is_none() || lv.labels);
+	}
+}
+
+fn spawn_flag(cmd: &mut Commands, pal: &Palette, pos: Vec2) {
+	cmd.spawn((
+		MaterialMesh2dBundle {
+			mesh: pal.flag_mesh.clone().into(),
+			material: pal.flag_material.clone(),
+			transform: Transform::from_translation(pos.extend(FLAG_Z)),
+			..default()
+		},
+		ScreenScaled::at(pos),
+		Layer::Border,
+		Item2D
+	));
+}
+
+fn spawn_point(cmd: &mut Commands, pal: &Palette, layer: Layer, material: &Handle<ColorMaterial>, pos: Vec2) {
+	cmd.spawn((
+		MaterialMesh2dBundle {
+			mesh: pal.point_mesh.clone().into(),
+			material: material.clone(),
+			transform: Transform::from_translation(pos.extend(POINT_Z)),
+			..default()
+		},
+		ScreenScaled::at(pos),
+		layer,
+		Item2D
+	));
+}
+
+fn spawn_label(cmd: &mut Commands, pal: &Palette, layer: Layer, pos: Vec2, text: String) {
+	cmd.spawn((
+		Text2dBundle {
+			text: Text::from_section(text, TextStyle {
+				font: pal.font.clone(),
+				font_size: LABEL_FONT_SIZE,
+				color: Color::WHITE
+			}).with_alignment(TextAlignment::BOTTOM_CENTER),
+			transform: Transform::from_translation(pos.extend(LABEL_Z)),
+			..default()
+		},
+		ScreenScaled {
+			pos: pos,
+			offset: Vec2::new(0.0, LABEL_OFFSET)
+		},
+		layer,
+		Label,
+		Item2D
+	));
+}
+
+fn spawn_arrow(cmd: &mut Commands, pal: &Palette, mesh: Handle<Mesh>, pos: Vec2, dir: f64) {
+	cmd.spawn((
+		MaterialMesh2dBundle {
+			mesh: mesh.into(),
+			material: pal.flow_material.clone(),
+			transform: Transform::from_translation(pos.extend(POINT_Z)).with_rotation(arrow::rotation(dir)),
+			..default()
+		},
+		ScreenScaled::at(pos),
+		Layer::Flow,
+		Item2D
+	));
+}
+
+fn redraw(mut cmd: Commands, data: Res<Data>, ctld: Res<Control>, config: Res<Config>, proj: Res<Projection>, pal: Res<Palette>, mut meshes: ResMut<Assets<Mesh>>, mut materials: ResMut<Assets<ColorMaterial>>, items: Query<Entity, With<Item2D>>, evr: EventReader<EventControlDataChanged>) {
+	if evr.is_empty() && !proj.is_changed() {
+		return;
+	}
+	evr.clear();
+	for e in &items {
+		cmd.entity(e).despawn_recursive();
+	}
+	let res = data.query_2d(&ctld.query());
+	if config.border.flag_outside {
+		let outside = res.photos(&data).map(|p| p.point)
+			.chain(res.temps(&data).map(|p| p.point))
+			.chain(res.flows(&data).map(|p| p.point))
+			.filter(|p| !data.bg.contains(p));
+		for p in outside {
+			spawn_flag(&mut cmd, &pal, proj.to_world(&p));
+		}
+	}
+	let solar_max = res.photos(&data).fold(0.0, |m, p| f64::max(m, p.solar));
+	for p in res.photos(&data) {
+		let material = materials.add(ColorMaterial::from(spectrum::photo_color(p.transparency, p.solar, solar_max)));
+		spawn_point(&mut cmd, &pal, Layer::Photo, &material, proj.to_world(&p.point));
+	}
+	let temp_range = config.temp_colormap.range(res.temps(&data).map(|t| t.val));
+	for t in res.temps(&data) {
+		let material = materials.add(ColorMaterial::from(config.temp_colormap.color(t.val, temp_range)));
+		let pos = proj.to_world(&t.point);
+		spawn_point(&mut cmd, &pal, Layer::Temp, &material, pos);
+		spawn_label(&mut cmd, &pal, Layer::Temp, pos, format!("{:.1}", t.val));
+	}
+	for f in res.flows(&data) {
+		let mesh = meshes.add(config.flow_arrow.mesh(f.speed));
+		let pos = proj.to_world(&f.point);
+		spawn_arrow(&mut cmd, &pal, mesh, pos, f.dir);
+		spawn_label(&mut cmd, &pal, Layer::Flow, pos, format!("{:.2}", f.speed));
+	}
+	cmd.insert_resource(Shown {
+		res: res,
+		stale: false
+	});
+}
@@ -0,0 +1,35 @@
+use bevy::{prelude::*, render::render_resource::PrimitiveTopology};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct BorderConfig {
+	/// Hide interpolated values outside of border.
+	pub mask_fields: bool,
+	/// Mark measurements outside of border.
+	pub flag_outside: bool
+}
+
+impl Default for BorderConfig {
+	fn default() -> Self {
+		Self {
+			mask_fields: false,
+			flag_outside: true
+		}
+	}
+}
+
+/// Line strip through `points`, last point connected to first if `closed`.
+pub fn polyline_mesh(points: &[Vec2], closed: bool) -> Mesh {
+	let mut positions: Vec<[f32; 3]> = points.iter().map(|p| [p.x, p.y, 0.0]).collect();
+	if closed && points.len() > 2 {
+		positions.push(positions[0]);
+	}
+	let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+	let uvs = vec![[0.0, 0.0]; positions.len()];
+
+	let mut mesh = Mesh::new(PrimitiveTopology::LineStrip);
+	mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+	mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+	mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+	mesh
+}
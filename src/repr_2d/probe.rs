@@ -0,0 +1,114 @@
+use bevy::prelude::*;
+use bevy_egui::{EguiContext, egui};
+use serde::{Deserialize, Serialize};
+
+use crate::frames::control::Control;
+use crate::utils::{config::Config, data_loader::{Data, Point}, projection::Projection};
+use super::{Shown, camera};
+
+const IDW_POWER: f32 = 2.0;
+const IDW_EXACT: f32 = 0.01;
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CoordFormat {
+	/// Decimal degrees.
+	Decimal,
+	/// Degrees, minutes, seconds.
+	Dms
+}
+
+impl Default for CoordFormat {
+	fn default() -> Self {
+		Self::Decimal
+	}
+}
+
+pub fn format_coord(p: &Point, fmt: CoordFormat) -> String {
+	match fmt {
+		CoordFormat::Decimal => format!("{:.6}, {:.6}", p.latitude, p.longitude),
+		CoordFormat::Dms => format!("{} {}", format_dms(p.latitude, 'N', 'S'), format_dms(p.longitude, 'E', 'W'))
+	}
+}
+
+fn format_dms(deg: f64, pos: char, neg: char) -> String {
+	let hemi = if deg < 0.0 {neg} else {pos};
+	let total = (deg.abs() * 3600.0 * 10.0).round() / 10.0;
+	let d = (total / 3600.0).floor();
+	let m = ((total - d * 3600.0) / 60.0).floor();
+	let s = total - d * 3600.0 - m * 60.0;
+	format!("{}°{:02}'{:04.1}\"{}", d, m, s, hemi)
+}
+
+/// Inverse distance weighted value at `at` from (position, value) samples.
+pub fn idw(at: Vec2, samples: impl Iterator<Item = (Vec2, f32)>) -> Option<f32> {
+	let mut wsum = 0.0;
+	let mut vsum = 0.0;
+	for (pos, val) in samples {
+		let d = pos.distance(at);
+		if d < IDW_EXACT {
+			return Some(val);
+		}
+		let w = 1.0 / d.powf(IDW_POWER);
+		wsum += w;
+		vsum += w * val;
+	}
+	if wsum > 0.0 {Some(vsum / wsum)} else {None}
+}
+
+pub fn show(
+	windows: Res<Windows>,
+	mut ctx: ResMut<EguiContext>,
+	cams: Query<(&Transform, &OrthographicProjection), With<Camera2d>>,
+	proj: Res<Projection>,
+	shown: Res<Shown>,
+	data: Res<Data>,
+	ctld: Res<Control>,
+	config: Res<Config>
+) {
+	let egui_ctx = ctx.ctx_mut();
+	if egui_ctx.wants_pointer_input() || egui_ctx.is_pointer_over_area() {
+		return;
+	}
+	let window = match windows.get_primary() {
+		Some(w) => w,
+		None => return
+	};
+	let (tf, ortho) = match cams.get_single() {
+		Ok(cam) => cam,
+		Err(_) => return
+	};
+	let at = match camera::cursor_world(window, tf, ortho) {
+		Some(at) => at,
+		None => return
+	};
+	let point = proj.to_point(at);
+	let masked = config.border.mask_fields && !data.bg.contains(&point);
+	let res = &shown.res;
+
+	egui::show_tooltip_at_pointer(egui_ctx, egui::Id::new("PROBE"), |ui| {
+		ui.label(format_coord(&point, config.coord_format));
+		ui.label(format!("{}, deep {:.2}", config.time_display.format(ctld.timestamp), ctld.deep));
+		if masked {
+			ui.label("Outside border");
+			return;
+		}
+		let temp = idw(at, res.temps(&data).map(|t| (proj.to_world(&t.point), t.val as f32)));
+		if let Some(t) = temp {
+			ui.label(format!("Temp: {:.2}", t));
+		}
+		// Interpolate flow as vector, direction is degrees clockwise from north.
+		let u = idw(at, res.flows(&data).map(|f| (proj.to_world(&f.point), (f.speed * f.dir.to_radians().sin()) as f32)));
+		let v = idw(at, res.flows(&data).map(|f| (proj.to_world(&f.point), (f.speed * f.dir.to_radians().cos()) as f32)));
+		if let (Some(u), Some(v)) = (u, v) {
+			let dir = u.atan2(v).to_degrees().rem_euclid(360.0);
+			ui.label(format!("Flow: {:.2}, {:.0}°", Vec2::new(u, v).length(), dir));
+		}
+		let nearest = res.photos(&data)
+			.map(|p| (proj.to_world(&p.point).distance(at), p))
+			.min_by(|a, b| a.0.total_cmp(&b.0));
+		if let Some((d, p)) = nearest {
+			ui.label(format!("Photo: solar {:.2}, {} wavelengths, {:.0} m away", p.solar, p.transparency.len(), d));
+		}
+	});
+}
@@ -0,0 +1,126 @@
+use bevy::prelude::Color;
+
+const WL_MIN: f64 = 380.0;
+const WL_MAX: f64 = 780.0;
+const WL_STEP: f64 = 5.0;
+
+/// D65 reference white (Y = 1).
+const WHITE_X: f64 = 0.95047;
+const WHITE_Z: f64 = 1.08883;
+
+const XYZ_TO_LINEAR_SRGB: [[f64; 3]; 3] = [
+	[3.2406, -1.5372, -0.4986],
+	[-0.9689, 1.8758, 0.0415],
+	[0.0557, -0.2040, 1.0570]
+];
+
+/// Piecewise gaussian with different widths left and right of the mean.
+fn g(wl: f64, mu: f64, sigma_l: f64, sigma_r: f64) -> f64 {
+	let sigma = if wl < mu {sigma_l} else {sigma_r};
+	let t = (wl - mu) / sigma;
+	(-0.5 * t * t).exp()
+}
+
+/// CIE 1931 2° color matching functions, multi-lobe gaussian fit (Wyman, Sloan, Shirley 2013).
+pub fn cie_xyz(wl: f64) -> (f64, f64, f64) {
+	let x = 1.056 * g(wl, 599.8, 37.9, 31.0) + 0.362 * g(wl, 442.0, 16.0, 26.7) - 0.065 * g(wl, 501.1, 20.4, 26.2);
+	let y = 0.821 * g(wl, 568.8, 46.9, 40.5) + 0.286 * g(wl, 530.9, 16.3, 31.1);
+	let z = 1.217 * g(wl, 437.0, 11.8, 36.0) + 0.681 * g(wl, 459.0, 26.0, 13.8);
+	(x, y, z)
+}
+
+/// Linear interpolation over (wavelength, value) pairs sorted by wavelength,
+/// values outside the sampled range are held at the nearest edge.
+fn sample(spec: &[(f64, f64)], wl: f64) -> f64 {
+	let first = spec[0];
+	let last = spec[spec.len() - 1];
+	if wl <= first.0 {
+		return first.1;
+	}
+	if wl >= last.0 {
+		return last.1;
+	}
+	let i = spec.partition_point(|p| p.0 <= wl);
+	let a = spec[i - 1];
+	let b = spec[i];
+	if b.0 - a.0 <= 0.0 {
+		return a.1;
+	}
+	a.1 + (b.1 - a.1) * (wl - a.0) / (b.0 - a.0)
+}
+
+/// XYZ of light passed through `spec` transparency under equal-energy illuminant,
+/// adapted so that a flat spectrum of 1.0 gives D65 white.
+pub fn spectrum_to_xyz(spec: &[(f64, f64)]) -> (f64, f64, f64) {
+	if spec.is_empty() {
+		return (0.0, 0.0, 0.0);
+	}
+	let mut spec = spec.to_vec();
+	spec.sort_by(|a, b| a.0.total_cmp(&b.0));
+	let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+	let (mut wx, mut wy, mut wz) = (0.0, 0.0, 0.0);
+	let mut wl = WL_MIN;
+	while wl <= WL_MAX {
+		let (cx, cy, cz) = cie_xyz(wl);
+		let v = sample(&spec, wl);
+		x += v * cx;
+		y += v * cy;
+		z += v * cz;
+		wx += cx;
+		wy += cy;
+		wz += cz;
+		wl += WL_STEP;
+	}
+	(x / wx * WHITE_X, y / wy, z / wz * WHITE_Z)
+}
+
+fn gamma(c: f64) -> f64 {
+	if c <= 0.0031308 {
+		12.92 * c
+	} else {
+		1.055 * c.powf(1.0 / 2.4) - 0.055
+	}
+}
+
+/// Gamma encoded sRGB components in [0, 1], out of gamut values are clipped.
+pub fn xyz_to_srgb(xyz: (f64, f64, f64)) -> [f32; 3] {
+	let v = [xyz.0, xyz.1, xyz.2];
+	let mut rgb = [0.0; 3];
+	for (i, row) in XYZ_TO_LINEAR_SRGB.iter().enumerate() {
+		let lin = row[0] * v[0] + row[1] * v[1] + row[2] * v[2];
+		rgb[i] = gamma(lin.clamp(0.0, 1.0)) as f32;
+	}
+	rgb
+}
+
+pub fn spectrum_to_srgb(spec: &[(f64, f64)]) -> [f32; 3] {
+	xyz_to_srgb(spectrum_to_xyz(spec))
+}
+
+/// Color of Photo record: transparency spectrum as RGB, `solar` relative to `solar_max` as alpha.
+pub fn photo_color(spec: &[(f64, f64)], solar: f64, solar_max: f64) -> Color {
+	let rgb = spectrum_to_srgb(spec);
+	let alpha = if solar_max > 0.0 {(solar / solar_max).clamp(0.0, 1.0)} else {1.0};
+	Color::rgba(rgb[0], rgb[1], rgb[2], alpha as f32)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const GREY_TOLERANCE: f32 = 0.01;
+
+	#[test]
+	fn flat_spectrum_is_grey() {
+		for v in [0.2, 0.5, 1.0] {
+			let [r, g, b] = spectrum_to_srgb(&[(WL_MIN, v), (WL_MAX, v)]);
+			assert!((r - g).abs() < GREY_TOLERANCE && (g - b).abs() < GREY_TOLERANCE, "{} gives ({}, {}, {})", v, r, g, b);
+		}
+	}
+
+	#[test]
+	fn narrow_peak_at_650_is_red() {
+		let [r, g, b] = spectrum_to_srgb(&[(WL_MIN, 0.0), (640.0, 0.0), (650.0, 1.0), (660.0, 0.0), (WL_MAX, 0.0)]);
+		assert!(r > 0.1 && r > 5.0 * g && r > 5.0 * b, "({}, {}, {})", r, g, b);
+	}
+}
@@ -0,0 +1,325 @@
+use super::data_loader::{Point, Photo, Temp, Flow};
+
+/// Position and time columns shared by all channels.
+#[derive(Default)]
+pub struct BaseColumns {
+	pub latitude: Vec<f64>,
+	pub longitude: Vec<f64>,
+	pub deep: Vec<f64>,
+	pub timestamp: Vec<i64>
+}
+
+impl BaseColumns {
+	pub fn len(&self) -> usize {
+		self.timestamp.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	pub fn point(&self, i: usize) -> Point {
+		Point {
+			latitude: self.latitude[i],
+			longitude: self.longitude[i],
+			deep: self.deep[i]
+		}
+	}
+
+	fn push(&mut self, point: &Point, timestamp: i64) {
+		self.latitude.push(point.latitude);
+		self.longitude.push(point.longitude);
+		self.deep.push(point.deep);
+		self.timestamp.push(timestamp);
+	}
+
+	fn append(&mut self, other: &mut BaseColumns) {
+		self.latitude.append(&mut other.latitude);
+		self.longitude.append(&mut other.longitude);
+		self.deep.append(&mut other.deep);
+		self.timestamp.append(&mut other.timestamp);
+	}
+
+	fn clear(&mut self) {
+		self.latitude.clear();
+		self.longitude.clear();
+		self.deep.clear();
+		self.timestamp.clear();
+	}
+}
+
+/// Photo record borrowed from `PhotoColumns`.
+pub struct PhotoRef<'a> {
+	pub point: Point,
+	pub timestamp: i64,
+	pub solar: f64,
+	pub transparency: &'a [(f64, f64)]
+}
+
+impl<'a> PhotoRef<'a> {
+	pub fn to_photo(&self) -> Photo {
+		Photo {
+			point: self.point,
+			timestamp: self.timestamp,
+			solar: self.solar,
+			transparency: self.transparency.to_vec()
+		}
+	}
+}
+
+/// Photos by columns, spectra of all photos in one buffer,
+/// spectrum of photo `i` is `spectra[offsets[i]..offsets[i + 1]]`.
+pub struct PhotoColumns {
+	pub base: BaseColumns,
+	pub solar: Vec<f64>,
+	pub spectra: Vec<(f64, f64)>,
+	pub offsets: Vec<usize>
+}
+
+impl Default for PhotoColumns {
+	fn default() -> Self {
+		Self {
+			base: BaseColumns::default(),
+			solar: Vec::new(),
+			spectra: Vec::new(),
+			offsets: vec![0]
+		}
+	}
+}
+
+impl PhotoColumns {
+	pub fn len(&self) -> usize {
+		self.base.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	pub fn get(&self, i: usize) -> Option<PhotoRef<'_>> {
+		if i >= self.len() {
+			return None;
+		}
+		Some(PhotoRef {
+			point: self.base.point(i),
+			timestamp: self.base.timestamp[i],
+			solar: self.solar[i],
+			transparency: &self.spectra[self.offsets[i]..self.offsets[i + 1]]
+		})
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = PhotoRef<'_>> + '_ {
+		(0..self.len()).filter_map(move |i| self.get(i))
+	}
+
+	pub fn push(&mut self, photo: Photo) {
+		self.base.push(&photo.point, photo.timestamp);
+		self.solar.push(photo.solar);
+		self.spectra.extend_from_slice(&photo.transparency);
+		self.offsets.push(self.spectra.len());
+	}
+
+	pub fn append(&mut self, other: &mut PhotoColumns) {
+		let shift = self.spectra.len();
+		self.base.append(&mut other.base);
+		self.solar.append(&mut other.solar);
+		self.spectra.append(&mut other.spectra);
+		self.offsets.extend(other.offsets.iter().skip(1).map(|o| o + shift));
+		other.offsets = vec![0];
+	}
+
+	pub fn clear(&mut self) {
+		*self = Self::default();
+	}
+}
+
+impl From<Vec<Photo>> for PhotoColumns {
+	fn from(photo: Vec<Photo>) -> Self {
+		let mut cols = Self::default();
+		for p in photo {
+			cols.push(p);
+		}
+		cols
+	}
+}
+
+#[derive(Default)]
+pub struct TempColumns {
+	pub base: BaseColumns,
+	pub val: Vec<f64>
+}
+
+impl TempColumns {
+	pub fn len(&self) -> usize {
+		self.base.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	pub fn get(&self, i: usize) -> Option<Temp> {
+		if i >= self.len() {
+			return None;
+		}
+		Some(Temp {
+			point: self.base.point(i),
+			timestamp: self.base.timestamp[i],
+			val: self.val[i]
+		})
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = Temp> + '_ {
+		(0..self.len()).filter_map(move |i| self.get(i))
+	}
+
+	pub fn push(&mut self, temp: Temp) {
+		self.base.push(&temp.point, temp.timestamp);
+		self.val.push(temp.val);
+	}
+
+	pub fn append(&mut self, other: &mut TempColumns) {
+		self.base.append(&mut other.base);
+		self.val.append(&mut other.val);
+	}
+
+	pub fn clear(&mut self) {
+		self.base.clear();
+		self.val.clear();
+	}
+}
+
+impl From<Vec<Temp>> for TempColumns {
+	fn from(temp: Vec<Temp>) -> Self {
+		let mut cols = Self::default();
+		for p in temp {
+			cols.push(p);
+		}
+		cols
+	}
+}
+
+#[derive(Default)]
+pub struct FlowColumns {
+	pub base: BaseColumns,
+	pub speed: Vec<f64>,
+	pub dir: Vec<f64>
+}
+
+impl FlowColumns {
+	pub fn len(&self) -> usize {
+		self.base.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	pub fn get(&self, i: usize) -> Option<Flow> {
+		if i >= self.len() {
+			return None;
+		}
+		Some(Flow {
+			point: self.base.point(i),
+			timestamp: self.base.timestamp[i],
+			speed: self.speed[i],
+			dir: self.dir[i]
+		})
+	}
+
+	pub fn iter(&self) -> impl Iterator<Item = Flow> + '_ {
+		(0..self.len()).filter_map(move |i| self.get(i))
+	}
+
+	pub fn push(&mut self, flow: Flow) {
+		self.base.push(&flow.point, flow.timestamp);
+		self.speed.push(flow.speed);
+		self.dir.push(flow.dir);
+	}
+
+	pub fn append(&mut self, other: &mut FlowColumns) {
+		self.base.append(&mut other.base);
+		self.speed.append(&mut other.speed);
+		self.dir.append(&mut other.dir);
+	}
+
+	pub fn clear(&mut self) {
+		self.base.clear();
+		self.speed.clear();
+		self.dir.clear();
+	}
+}
+
+impl From<Vec<Flow>> for FlowColumns {
+	fn from(flow: Vec<Flow>) -> Self {
+		let mut cols = Self::default();
+		for p in flow {
+			cols.push(p);
+		}
+		cols
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::utils::data_loader::{Data, DataFile, Background};
+
+	fn point(i: usize) -> Point {
+		Point {latitude: 55.0 + i as f64 * 0.01, longitude: 37.0, deep: i as f64}
+	}
+
+	fn photo(i: usize, spectrum: usize) -> Photo {
+		Photo {
+			point: point(i),
+			timestamp: 1_600_000_000 + i as i64 * 60,
+			solar: i as f64 * 10.0,
+			transparency: (0..spectrum).map(|k| (400.0 + k as f64 * 10.0, k as f64 / 10.0)).collect()
+		}
+	}
+
+	fn file() -> DataFile {
+		DataFile {
+			bg: Background::default(),
+			photo: vec![photo(0, 3), photo(1, 0), photo(2, 5)],
+			temp: (0..4).map(|i| Temp {point: point(i), timestamp: 1_600_000_000 + i as i64, val: 4.0 + i as f64}).collect(),
+			flow: (0..2).map(|i| Flow {point: point(i), timestamp: 1_600_000_000 + i as i64, speed: 0.5, dir: i as f64 * 90.0}).collect()
+		}
+	}
+
+	#[test]
+	fn spectra_offsets() {
+		let cols = PhotoColumns::from(file().photo);
+		assert_eq!(cols.offsets, vec![0, 3, 3, 8]);
+		assert_eq!(cols.spectra.len(), 8);
+		assert!(cols.get(1).unwrap().transparency.is_empty());
+		assert!(cols.get(3).is_none());
+	}
+
+	#[test]
+	fn append_shifts_offsets() {
+		let mut cols = PhotoColumns::from(file().photo);
+		let mut other = PhotoColumns::from(vec![photo(3, 2), photo(4, 1)]);
+		cols.append(&mut other);
+		assert_eq!(cols.offsets, vec![0, 3, 3, 8, 10, 11]);
+		assert!(other.is_empty() && other.base.is_empty());
+		assert_eq!(cols.get(4).unwrap().transparency, &[(400.0, 0.0)]);
+	}
+
+	#[test]
+	fn data_file_round_trip() {
+		let data = Data::from(file());
+		assert_eq!(data.photo.offsets, vec![0, 3, 3, 8]);
+		let back = data.to_file();
+		let orig = file();
+		assert_eq!(back.photo.len(), orig.photo.len());
+		for (a, b) in back.photo.iter().zip(&orig.photo) {
+			assert_eq!((a.timestamp, a.solar, a.point.deep), (b.timestamp, b.solar, b.point.deep));
+			assert_eq!(a.transparency, b.transparency);
+		}
+		assert_eq!(back.temp.iter().map(|t| (t.timestamp, t.val)).collect::<Vec<_>>(),
+			orig.temp.iter().map(|t| (t.timestamp, t.val)).collect::<Vec<_>>());
+		assert_eq!(back.flow.iter().map(|f| (f.point.latitude, f.speed, f.dir)).collect::<Vec<_>>(),
+			orig.flow.iter().map(|f| (f.point.latitude, f.speed, f.dir)).collect::<Vec<_>>());
+	}
+}
@@ -1,14 +1,37 @@
-use std::{io::{Error, Read, ErrorKind}, fs::File};
+use std::{io::{Error, Read, ErrorKind}, fs::{self, File}};
 use bevy::prelude::Resource;
 use serde::{Deserialize, Serialize};
+use toml_edit::{Document, Item, Table, ArrayOfTables, Array, InlineTable};
 
-use crate::frames::control::Deltas;
+use crate::frames::{control::{Deltas, LayerVisibility, PlaybackConfig, DeepLevelsConfig}, timeline::TimeDisplay};
+use crate::repr_2d::{colormap::ColormapConfig, arrow::ArrowConfig, outline::BorderConfig, probe::CoordFormat};
+use super::{projection::ProjectionKind, import::ImportConfig};
 
 pub const CONFIG_PATH: &str = "./config.toml";
 
 #[derive(Resource, Deserialize, Serialize)]
 pub struct Config {
-	pub default_deltas: Deltas
+	pub default_deltas: Deltas,
+	#[serde(default)]
+	pub temp_colormap: ColormapConfig,
+	#[serde(default)]
+	pub flow_arrow: ArrowConfig,
+	#[serde(default)]
+	pub projection: ProjectionKind,
+	#[serde(default)]
+	pub border: BorderConfig,
+	#[serde(default)]
+	pub coord_format: CoordFormat,
+	#[serde(default)]
+	pub layers: LayerVisibility,
+	#[serde(default)]
+	pub time_display: TimeDisplay,
+	#[serde(default)]
+	pub playback: PlaybackConfig,
+	#[serde(default)]
+	pub deep_levels: DeepLevelsConfig,
+	#[serde(default)]
+	pub import: ImportConfig
 }
 
 pub fn load_config() -> Result<Config, Error> {
@@ -19,4 +42,142 @@ pub fn load_config() -> Result<Config, Error> {
 		Ok(c) => Ok(c),
 		Err(e) => Err(Error::new(ErrorKind::InvalidData, e.to_string()))
 	}
-}
\ No newline at end of file
+}
+
+/// Write keys changed from config file, comments and formatting of the rest are kept.
+pub fn save_config(config: &Config) -> Result<(), Error> {
+	let value = match toml::Value::try_from(config) {
+		Ok(toml::Value::Table(t)) => t,
+		Ok(_) => return Err(Error::new(ErrorKind::InvalidData, "config is not a table")),
+		Err(e) => return Err(Error::new(ErrorKind::InvalidData, e.to_string()))
+	};
+	let mut doc = match fs::read_to_string(CONFIG_PATH) {
+		Ok(raw) => match raw.parse::<Document>() {
+			Ok(doc) => doc,
+			Err(e) => return Err(Error::new(ErrorKind::InvalidData, e.to_string()))
+		},
+		Err(e) if e.kind() == ErrorKind::NotFound => Document::new(),
+		Err(e) => return Err(e)
+	};
+	merge(doc.as_table_mut(), &value);
+	fs::write(CONFIG_PATH, doc.to_string())
+}
+
+/// Value of `item` as parsed by `toml`, for comparison with config.
+fn item_value(key: &str, item: &Item) -> Option<toml::Value> {
+	let mut doc = Document::new();
+	doc[key] = item.clone();
+	let mut table: toml::value::Table = toml::from_str(&doc.to_string()).ok()?;
+	table.remove(key)
+}
+
+fn to_value(value: &toml::Value) -> toml_edit::Value {
+	match value {
+		toml::Value::String(s) => toml_edit::Value::from(s),
+		toml::Value::Integer(i) => toml_edit::Value::from(*i),
+		toml::Value::Float(f) => toml_edit::Value::from(*f),
+		toml::Value::Boolean(b) => toml_edit::Value::from(*b),
+		toml::Value::Datetime(dt) => match dt.to_string().parse::<toml_edit::Datetime>() {
+			Ok(dt) => toml_edit::Value::from(dt),
+			Err(_) => toml_edit::Value::from(dt.to_string())
+		},
+		toml::Value::Array(a) => toml_edit::Value::from(a.iter().map(to_value).collect::<Array>()),
+		toml::Value::Table(t) => toml_edit::Value::from(t.iter().map(|(k, v)| (k, to_value(v))).collect::<InlineTable>())
+	}
+}
+
+/// Tables of non empty array, written as `[[key]]`.
+fn tables(value: &toml::Value) -> Option<Vec<&toml::value::Table>> {
+	match value {
+		toml::Value::Array(a) if !a.is_empty() => a.iter().map(|v| v.as_table()).collect(),
+		_ => None
+	}
+}
+
+fn to_table(value: &toml::value::Table) -> Table {
+	let mut table = Table::new();
+	for (k, v) in value {
+		table[k] = to_item(v);
+	}
+	table
+}
+
+fn to_item(value: &toml::Value) -> Item {
+	if let toml::Value::Table(t) = value {
+		return Item::Table(to_table(t));
+	}
+	match tables(value) {
+		Some(ts) => {
+			let mut array = ArrayOfTables::new();
+			for t in ts {
+				array.push(to_table(t));
+			}
+			Item::ArrayOfTables(array)
+		},
+		None => Item::Value(to_value(value))
+	}
+}
+
+/// Replace values of `table` that differ from `new`, tables are merged recursively.
+fn merge(table: &mut Table, new: &toml::value::Table) {
+	for (key, value) in new {
+		match (table.get_mut(key), value) {
+			(Some(Item::Table(old)), toml::Value::Table(value)) => {
+				merge(old, value);
+				continue;
+			},
+			(Some(Item::ArrayOfTables(old)), value) => if let Some(ts) = tables(value) {
+				while old.len() > ts.len() {
+					old.remove(old.len() - 1);
+				}
+				for (i, t) in ts.into_iter().enumerate() {
+					match old.get_mut(i) {
+						Some(old) => merge(old, t),
+						None => old.push(to_table(t))
+					}
+				}
+				continue;
+			},
+			_ => ()
+		}
+		if table.get(key).and_then(|old| item_value(key, old)).as_ref() == Some(value) {
+			continue;
+		}
+		let mut item = to_item(value);
+		// Keep comment after replaced value.
+		if let (Some(Item::Value(old)), Item::Value(v)) = (table.get(key), &mut item) {
+			*v.decor_mut() = old.decor().clone();
+		}
+		table[key] = item;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const RAW: &str = "# Deltas of query\n[default_deltas]\ntimestamp = 86_400 # one day\ndeep = 1.0\n\n[[profiles]]\nname = \"a\"\n";
+
+	#[test]
+	fn unchanged_is_kept() {
+		let mut doc: Document = RAW.parse().unwrap();
+		let value: toml::value::Table = toml::from_str(RAW).unwrap();
+		merge(doc.as_table_mut(), &value);
+		assert_eq!(doc.to_string(), RAW);
+	}
+
+	#[test]
+	fn changed_keys_only() {
+		let mut doc: Document = RAW.parse().unwrap();
+		let mut value: toml::value::Table = toml::from_str(RAW).unwrap();
+		value.get_mut("default_deltas").unwrap()["deep"] = toml::Value::Float(2.5);
+		let profiles = value.get_mut("profiles").unwrap().as_array_mut().unwrap();
+		let mut b = profiles[0].clone();
+		b["name"] = toml::Value::String(String::from("b"));
+		profiles.push(b);
+		merge(doc.as_table_mut(), &value);
+		let out = doc.to_string();
+		assert!(out.starts_with("# Deltas of query\n[default_deltas]\ntimestamp = 86_400 # one day\ndeep = 2.5\n"), "{}", out);
+		assert_eq!(toml::from_str::<toml::value::Table>(&out).unwrap(), value);
+	}
+}
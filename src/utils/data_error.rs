@@ -0,0 +1,154 @@
+use std::{fmt, io, path::PathBuf};
+use rmp_serde as rmps;
+
+use super::progress::Cancelled;
+
+/// Failure of data file load or save.
+pub enum DataError {
+	Io(PathBuf, io::Error),
+	UnsupportedExtension(String),
+	Xlsx(calamine::XlsxError),
+	Csv(csv::Error),
+	/// Photo, temp or flow sheet of profile not found, names of sheets in book
+	/// or channel types of long CSV.
+	UnmatchedSheets(Vec<String>),
+	/// Channel of per channel CSV not found in file name.
+	UnknownChannel(String),
+	/// Row and column are zero based, displayed as in spreadsheet, e.g. `photo!F12`.
+	BadCell {
+		sheet: String,
+		row: usize,
+		col: usize,
+		expected: &'static str,
+		found: String
+	},
+	Decode(rmps::decode::Error),
+	Encode(rmps::encode::Error),
+	Cancelled
+}
+
+/// Spreadsheet column name of zero based index: A, B, ..., Z, AA, ...
+pub fn column_name(col: usize) -> String {
+	let mut name = Vec::new();
+	let mut n = col + 1;
+	while n > 0 {
+		name.push(b'A' + ((n - 1) % 26) as u8);
+		n = (n - 1) / 26;
+	}
+	name.reverse();
+	String::from_utf8(name).unwrap()
+}
+
+impl DataError {
+	/// Cell of problem as in spreadsheet, e.g. `photo!F12`.
+	pub fn location(&self) -> Option<String> {
+		match self {
+			DataError::BadCell {sheet, row, col, ..} => Some(format!("{}!{}{}", sheet, column_name(*col), row + 1)),
+			_ => None
+		}
+	}
+
+	/// Description without location.
+	pub fn reason(&self) -> String {
+		match self {
+			DataError::BadCell {expected, found, ..} => format!("expected {}, found {}", expected, found),
+			oth => oth.to_string()
+		}
+	}
+}
+
+impl fmt::Display for DataError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			DataError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+			DataError::UnsupportedExtension(ext) => write!(f, "Extension '{}' of selected file not supported, supported list: [dat, xlsx, csv, tsv]", ext),
+			DataError::Xlsx(e) => write!(f, "Fail to read xlsx: {}", e),
+			DataError::Csv(e) => write!(f, "Fail to read csv: {}", e),
+			DataError::UnmatchedSheets(names) => write!(f, "Photo, temp or flow sheet not found, available: {:?}", names),
+			DataError::UnknownChannel(name) => write!(f, "Channel of '{}' not found in file name", name),
+			DataError::BadCell {..} => write!(f, "{}: {}", self.location().unwrap_or_default(), self.reason()),
+			DataError::Decode(e) => write!(f, "Decode error: {}", e),
+			DataError::Encode(e) => write!(f, "Encode error: {}", e),
+			DataError::Cancelled => write!(f, "Cancelled")
+		}
+	}
+}
+
+impl fmt::Debug for DataError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Display::fmt(self, f)
+	}
+}
+
+impl std::error::Error for DataError {}
+
+impl From<calamine::XlsxError> for DataError {
+	fn from(e: calamine::XlsxError) -> Self {
+		DataError::Xlsx(e)
+	}
+}
+
+impl From<csv::Error> for DataError {
+	fn from(e: csv::Error) -> Self {
+		DataError::Csv(e)
+	}
+}
+
+impl From<rmps::decode::Error> for DataError {
+	fn from(e: rmps::decode::Error) -> Self {
+		DataError::Decode(e)
+	}
+}
+
+impl From<rmps::encode::Error> for DataError {
+	fn from(e: rmps::encode::Error) -> Self {
+		DataError::Encode(e)
+	}
+}
+
+impl From<Cancelled> for DataError {
+	fn from(_: Cancelled) -> Self {
+		DataError::Cancelled
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn bad_cell() -> DataError {
+		DataError::BadCell {
+			sheet: String::from("photo"),
+			row: 11,
+			col: 5,
+			expected: "number",
+			found: String::from("\"abc\"")
+		}
+	}
+
+	#[test]
+	fn column_names() {
+		assert_eq!(column_name(0), "A");
+		assert_eq!(column_name(25), "Z");
+		assert_eq!(column_name(26), "AA");
+		assert_eq!(column_name(27), "AB");
+		assert_eq!(column_name(701), "ZZ");
+		assert_eq!(column_name(702), "AAA");
+	}
+
+	#[test]
+	fn bad_cell_location_and_reason() {
+		let e = bad_cell();
+		assert_eq!(e.location().as_deref(), Some("photo!F12"));
+		assert_eq!(e.reason(), "expected number, found \"abc\"");
+		assert_eq!(e.to_string(), "photo!F12: expected number, found \"abc\"");
+	}
+
+	#[test]
+	fn no_location_without_cell() {
+		let e = DataError::UnsupportedExtension(String::from("txt"));
+		assert_eq!(e.location(), None);
+		assert_eq!(e.reason(), e.to_string());
+		assert!(matches!(DataError::from(Cancelled), DataError::Cancelled));
+	}
+}
@@ -0,0 +1,52 @@
+/// Records in one time slice of `ChannelIndex`.
+const SLICE_LEN: usize = 256;
+
+/// Record indices of one channel in time slices of `SLICE_LEN` records
+/// ordered by timestamp, every slice is sorted by depth.
+#[derive(Default)]
+pub struct ChannelIndex {
+	/// First and last timestamp of every slice.
+	slices: Vec<(i64, i64)>,
+	deeps: Vec<f64>,
+	order: Vec<usize>
+}
+
+impl ChannelIndex {
+	/// Index of records given as (timestamp, depth).
+	pub fn build(records: impl Iterator<Item = (i64, f64)>) -> Self {
+		let mut records: Vec<(i64, f64, usize)> = records.enumerate().map(|(i, (ts, deep))| (ts, deep, i)).collect();
+		records.sort_unstable_by_key(|r| (r.0, r.2));
+		let mut slices = Vec::new();
+		for slice in records.chunks_mut(SLICE_LEN) {
+			slices.push((slice[0].0, slice[slice.len() - 1].0));
+			slice.sort_unstable_by(|a, b| a.1.total_cmp(&b.1));
+		}
+		Self {
+			slices: slices,
+			deeps: records.iter().map(|r| r.1).collect(),
+			order: records.iter().map(|r| r.2).collect()
+		}
+	}
+
+	/// Indices of records in slices overlapping [`from`, `to`] with depth in
+	/// [`deep_min`, `deep_max`], timestamps of edge slices must be checked by caller.
+	pub fn candidates(&self, from: i64, to: i64, deep_min: f64, deep_max: f64) -> impl Iterator<Item = usize> + '_ {
+		let a = self.slices.partition_point(|s| s.1 < from);
+		let b = self.slices.partition_point(|s| s.0 <= to).max(a);
+		(a..b).flat_map(move |s| {
+			let start = s * SLICE_LEN;
+			let deeps = &self.deeps[start..(start + SLICE_LEN).min(self.deeps.len())];
+			let lo = deeps.partition_point(|d| *d < deep_min);
+			let hi = deeps.partition_point(|d| *d <= deep_max).max(lo);
+			self.order[start + lo..start + hi].iter().copied()
+		})
+	}
+}
+
+/// Time and depth index over photo, temp and flow, rebuilt by `Data::add` and `Data::clear`.
+#[derive(Default)]
+pub struct DataIndex {
+	pub photo: ChannelIndex,
+	pub temp: ChannelIndex,
+	pub flow: ChannelIndex
+}
@@ -1,27 +1,44 @@
 use std::{fs::{File, OpenOptions}, path::PathBuf, io::BufReader};
+use chrono::{TimeZone, Utc, SecondsFormat};
 use bevy::prelude::Resource;
 use rmp_serde as rmps;
 use serde::{Deserialize, Serialize};
 use calamine::{open_workbook, Xlsx, Reader};
 
-use super::{in_delta_i64, in_delta_f64};
+use super::{in_delta_i64, in_delta_f64, in_polygon, data_index::{DataIndex, ChannelIndex}, columns::{BaseColumns, PhotoColumns, PhotoRef, TempColumns, FlowColumns}, progress::Progress, data_error::DataError, import::{self, ImportMode, ImportOptions, ImportProfile, ColumnMap, SourceTimezone, NumericTime, XLSX_SHEET_PHOTO, XLSX_SHEET_TEMP, XLSX_SHEET_FLOW}};
+
+const CSV_HEADER: [&str; 9] = ["type", "latitude", "longitude", "deep", "datetime", "solar", "temp", "speed", "dir"];
+const CSV_SPECTRUM_HEADER: [&str; 2] = ["wavelength", "transparency"];
+/// Margin of depth window over strict delta comparison in `Selection::contains`.
+const DEEP_WINDOW_PAD: f64 = 1e-6;
+/// Type of border points in long CSV.
+const CSV_TYPE_BORDER: &str = "border";
 
-const XLSX_SHEET_BG: &str = "bg";
-const XLSX_SHEET_PHOTO: &str = "photo";
-const XLSX_SHEET_TEMP: &str = "temp";
-const XLSX_SHEET_FLOW: &str = "flow";
-const XLSX_LATITUDE_INDEX: usize = 0;
-const XLSX_LONGITUDE_INDEX: usize = 1;
-const XLSX_DEEP_INDEX: usize = 2;
-const XLSX_DATETIME_INDEX: usize = 3;
-const XLSX_SPEC_INDEX: usize = 5;
 
 enum Extension {
 	Msgpack,
-	Xlsx
+	Xlsx,
+	/// Delimiter of fields.
+	Csv(u8)
 }
 
-#[derive(Deserialize, Serialize, Clone)]
+fn extension(path: &PathBuf) -> Result<Extension, DataError> {
+	match path.extension() {
+		Some(name) => {
+			let name = name.to_string_lossy().to_lowercase();
+			match name.as_str() {
+				"xlsx" => Ok(Extension::Xlsx),
+				"dat" => Ok(Extension::Msgpack),
+				"csv" => Ok(Extension::Csv(b',')),
+				"tsv" => Ok(Extension::Csv(b'\t')),
+				_ => Err(DataError::UnsupportedExtension(name))
+			}
+		},
+		None => Ok(Extension::Msgpack)
+	}
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy)]
 pub struct Point {
 	pub latitude: f64,
 	pub longitude: f64,
@@ -64,8 +81,13 @@ pub struct Flow {
 #[derive(Deserialize, Serialize, Clone)]
 pub struct BackgroundImage {
 	pub image_path: String,
+	/// Meters per image pixel.
 	pub scale: f64,
-	pub rotate: f64
+	/// Degrees clockwise.
+	pub rotate: f64,
+	/// Coordinates of image center, projection origin (mean position of data) if not set.
+	#[serde(default)]
+	pub anchor: Option<Point>
 }
 
 #[derive(Deserialize, Serialize, Clone)]
@@ -83,6 +105,13 @@ impl Default for Background {
 	}
 }
 
+impl Background {
+	/// True if point is inside border, or border is not defined.
+	pub fn contains(&self, p: &Point) -> bool {
+		self.border.len() < 3 || in_polygon(p, &self.border)
+	}
+}
+
 pub struct Query {
 	pub timestamp: i64,
 	pub timestamp_d: i64,
@@ -92,17 +121,123 @@ pub struct Query {
 	pub flow_deep_d: f64
 }
 
+/// Explicit time and depth window, bounds included.
+#[derive(Clone)]
+pub struct RangeQuery {
+	pub timestamp_start: i64,
+	pub timestamp_end: i64,
+	pub deep_min: f64,
+	pub deep_max: f64
+}
+
+impl Default for RangeQuery {
+	fn default() -> Self {
+		Self {
+			timestamp_start: 0,
+			timestamp_end: 0,
+			deep_min: 0.0,
+			deep_max: 0.0
+		}
+	}
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Channel {
+	Photo,
+	Temp,
+	Flow
+}
+
+/// Records selection: center with symmetric deltas, or explicit ranges.
+pub enum Selection {
+	Delta(Query),
+	Range(RangeQuery)
+}
+
+impl Selection {
+	/// Inclusive timestamp bounds of selected records.
+	pub fn time_window(&self) -> (i64, i64) {
+		match self {
+			Selection::Delta(q) => (q.timestamp - q.timestamp_d + 1, q.timestamp + q.timestamp_d - 1),
+			Selection::Range(r) => (r.timestamp_start, r.timestamp_end)
+		}
+	}
+
+	/// Inclusive depth bounds of selected records of channel, may be slightly wider.
+	pub fn deep_window(&self, channel: Channel) -> (f64, f64) {
+		match self {
+			Selection::Delta(q) => {
+				let deep_d = match channel {
+					Channel::Photo => q.photo_deep_d,
+					Channel::Temp => q.temp_deep_d,
+					Channel::Flow => q.flow_deep_d
+				};
+				(q.deep - deep_d - DEEP_WINDOW_PAD, q.deep + deep_d + DEEP_WINDOW_PAD)
+			},
+			Selection::Range(r) => (r.deep_min, r.deep_max)
+		}
+	}
+
+	pub fn contains(&self, timestamp: i64, deep: f64, channel: Channel) -> bool {
+		match self {
+			Selection::Delta(q) => {
+				let deep_d = match channel {
+					Channel::Photo => q.photo_deep_d,
+					Channel::Temp => q.temp_deep_d,
+					Channel::Flow => q.flow_deep_d
+				};
+				in_delta_i64(timestamp, q.timestamp, q.timestamp_d) && in_delta_f64(deep, q.deep, deep_d)
+			},
+			Selection::Range(r) => {
+				timestamp >= r.timestamp_start && timestamp <= r.timestamp_end && deep >= r.deep_min && deep <= r.deep_max
+			}
+		}
+	}
+}
+
+/// Indices of selected records in `Data` vectors.
 pub struct QueryResult {
-	pub photo: Vec<Photo>,
-	pub temp: Vec<Temp>,
-	pub flow: Vec<Flow>	
+	pub photo: Vec<usize>,
+	pub temp: Vec<usize>,
+	pub flow: Vec<usize>
+}
+
+impl QueryResult {
+	pub fn photos<'a>(&'a self, data: &'a Data) -> impl Iterator<Item = PhotoRef<'a>> + 'a {
+		self.photo.iter().filter_map(move |i| data.photo.get(*i))
+	}
+
+	pub fn temps<'a>(&'a self, data: &'a Data) -> impl Iterator<Item = Temp> + 'a {
+		self.temp.iter().filter_map(move |i| data.temp.get(*i))
+	}
+
+	pub fn flows<'a>(&'a self, data: &'a Data) -> impl Iterator<Item = Flow> + 'a {
+		self.flow.iter().filter_map(move |i| data.flow.get(*i))
+	}
+}
+
+impl Default for QueryResult {
+	fn default() -> Self {
+		Self {
+			photo: Vec::new(),
+			temp: Vec::new(),
+			flow: Vec::new()
+		}
+	}
 }
 
+#[derive(Clone)]
 pub struct Ranges {
 	pub deep_max: f64,
 	pub deep_min: f64,
 	pub timestamp_max: i64,
-	pub timestamp_min: i64
+	pub timestamp_min: i64,
+	pub temp_max: f64,
+	pub temp_min: f64,
+	pub speed_max: f64,
+	pub speed_min: f64,
+	pub wavelength_max: f64,
+	pub wavelength_min: f64
 }
 
 impl Default for Ranges {
@@ -111,296 +246,723 @@ impl Default for Ranges {
 			deep_max: 0.0,
 			deep_min: 0.0,
 			timestamp_max: 0,
-			timestamp_min: 0
+			timestamp_min: 0,
+			temp_max: 0.0,
+			temp_min: 0.0,
+			speed_max: 0.0,
+			speed_min: 0.0,
+			wavelength_max: 0.0,
+			wavelength_min: 0.0
 		}
 	}
 }
 
-#[derive(Deserialize, Serialize, Resource)]
-pub struct Data {
+/// Running (min, max) of data, empty while min > max.
+#[derive(Clone)]
+struct Bounds {
+	deep: (f64, f64),
+	timestamp: (i64, i64),
+	temp: (f64, f64),
+	speed: (f64, f64),
+	wavelength: (f64, f64)
+}
+
+impl Default for Bounds {
+	fn default() -> Self {
+		Self {
+			deep: (f64::MAX, f64::MIN),
+			timestamp: (i64::MAX, i64::MIN),
+			temp: (f64::MAX, f64::MIN),
+			speed: (f64::MAX, f64::MIN),
+			wavelength: (f64::MAX, f64::MIN)
+		}
+	}
+}
+
+fn grow_f64(r: &mut (f64, f64), v: f64) {
+	if v < r.0 {r.0 = v}
+	if v > r.1 {r.1 = v}
+}
+
+fn grow_i64(r: &mut (i64, i64), v: i64) {
+	if v < r.0 {r.0 = v}
+	if v > r.1 {r.1 = v}
+}
+
+fn or_zero_f64(r: (f64, f64)) -> (f64, f64) {
+	if r.0 > r.1 {(0.0, 0.0)} else {r}
+}
+
+impl Bounds {
+	fn add_border(&mut self, border: &[Point]) {
+		for p in border {
+			grow_f64(&mut self.deep, p.deep);
+		}
+	}
+
+	/// Grow by records of `photo` starting from `from`.
+	fn add_photo(&mut self, photo: &PhotoColumns, from: usize) {
+		self.add_base(&photo.base.deep[from..], &photo.base.timestamp[from..]);
+		for (wl, _) in &photo.spectra[photo.offsets[from]..] {
+			grow_f64(&mut self.wavelength, *wl);
+		}
+	}
+
+	fn add_temp(&mut self, temp: &TempColumns, from: usize) {
+		self.add_base(&temp.base.deep[from..], &temp.base.timestamp[from..]);
+		for v in &temp.val[from..] {
+			grow_f64(&mut self.temp, *v);
+		}
+	}
+
+	fn add_flow(&mut self, flow: &FlowColumns, from: usize) {
+		self.add_base(&flow.base.deep[from..], &flow.base.timestamp[from..]);
+		for v in &flow.speed[from..] {
+			grow_f64(&mut self.speed, *v);
+		}
+	}
+
+	fn add_base(&mut self, deep: &[f64], timestamp: &[i64]) {
+		for v in deep {
+			grow_f64(&mut self.deep, *v);
+		}
+		for v in timestamp {
+			grow_i64(&mut self.timestamp, *v);
+		}
+	}
+
+	fn ranges(&self) -> Ranges {
+		let deep = or_zero_f64(self.deep);
+		let timestamp = if self.timestamp.0 > self.timestamp.1 {(0, 0)} else {self.timestamp};
+		let temp = or_zero_f64(self.temp);
+		let speed = or_zero_f64(self.speed);
+		let wavelength = or_zero_f64(self.wavelength);
+		Ranges {
+			deep_max: deep.1,
+			deep_min: deep.0,
+			timestamp_max: timestamp.1,
+			timestamp_min: timestamp.0,
+			temp_max: temp.1,
+			temp_min: temp.0,
+			speed_max: speed.1,
+			speed_min: speed.0,
+			wavelength_max: wavelength.1,
+			wavelength_min: wavelength.0
+		}
+	}
+}
+
+/// Layout of `.dat` file, records stored row by row.
+#[derive(Deserialize, Serialize)]
+pub struct DataFile {
 	pub bg: Background,
 	pub photo: Vec<Photo>,
 	pub temp: Vec<Temp>,
 	pub flow: Vec<Flow>
 }
 
+/// Measurements stored by columns, see `columns` for record views.
+#[derive(Resource)]
+pub struct Data {
+	pub bg: Background,
+	pub photo: PhotoColumns,
+	pub temp: TempColumns,
+	pub flow: FlowColumns,
+	index: DataIndex,
+	bounds: Bounds
+}
+
 impl Default for Data {
 	fn default() -> Self {
 		Self {
 			bg: Background::default(),
-			photo: Vec::new(),
-			temp: Vec::new(),
-			flow: Vec::new()
+			photo: PhotoColumns::default(),
+			temp: TempColumns::default(),
+			flow: FlowColumns::default(),
+			index: DataIndex::default(),
+			bounds: Bounds::default()
 		}
 	}
 }
 
+impl From<DataFile> for Data {
+	fn from(file: DataFile) -> Self {
+		let mut data = Self {
+			bg: file.bg,
+			photo: PhotoColumns::from(file.photo),
+			temp: TempColumns::from(file.temp),
+			flow: FlowColumns::from(file.flow),
+			index: DataIndex::default(),
+			bounds: Bounds::default()
+		};
+		data.rebound();
+		data.reindex();
+		data
+	}
+}
+
 impl Data {
 	pub fn clear(&mut self) {
 		self.bg = Background::default();
 		self.photo.clear();
 		self.temp.clear();
 		self.flow.clear();
+		self.bounds = Bounds::default();
+		self.reindex();
+	}
+
+	fn reindex(&mut self) {
+		self.index = DataIndex {
+			photo: ChannelIndex::build(self.photo.base.timestamp.iter().copied().zip(self.photo.base.deep.iter().copied())),
+			temp: ChannelIndex::build(self.temp.base.timestamp.iter().copied().zip(self.temp.base.deep.iter().copied())),
+			flow: ChannelIndex::build(self.flow.base.timestamp.iter().copied().zip(self.flow.base.deep.iter().copied()))
+		};
+	}
+
+	/// Recompute bounds of all data.
+	fn rebound(&mut self) {
+		self.bounds = Bounds::default();
+		self.bounds.add_border(&self.bg.border);
+		self.bounds.add_photo(&self.photo, 0);
+		self.bounds.add_temp(&self.temp, 0);
+		self.bounds.add_flow(&self.flow, 0);
+	}
+
+	/// Row by row copy for `.dat` file.
+	pub fn to_file(&self) -> DataFile {
+		DataFile {
+			bg: self.bg.clone(),
+			photo: self.photo.iter().map(|p| p.to_photo()).collect(),
+			temp: self.temp.iter().collect(),
+			flow: self.flow.iter().collect()
+		}
+	}
+
+	/// Snapshot of measurements in query result, border and image are kept.
+	pub fn to_file_selected(&self, res: &QueryResult) -> DataFile {
+		DataFile {
+			bg: self.bg.clone(),
+			photo: res.photos(self).map(|p| p.to_photo()).collect(),
+			temp: res.temps(self).collect(),
+			flow: res.flows(self).collect()
+		}
+	}
+
+	/// Border and positions of all measurements.
+	pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+		self.bg.border.iter().copied()
+			.chain((0..self.photo.len()).map(|i| self.photo.base.point(i)))
+			.chain((0..self.temp.len()).map(|i| self.temp.base.point(i)))
+			.chain((0..self.flow.len()).map(|i| self.flow.base.point(i)))
 	}
 
-	pub fn add(&mut self, data_add: Data) -> (usize, usize, usize) {
+	pub fn add(&mut self, mut data_add: Data) -> (usize, usize, usize) {
 		let old_photos = self.photo.len();
 		let old_temps = self.temp.len();
 		let old_flows = self.flow.len();
-		for p in data_add.photo {
-			self.photo.push(p);
+		let border_replaced = !data_add.bg.border.is_empty();
+		if data_add.bg.image.is_some() {
+			self.bg.image = data_add.bg.image;
 		}
-		for p in data_add.temp {
-			self.temp.push(p);
-		}
-		for p in data_add.flow {
-			self.flow.push(p);
+		if border_replaced {
+			self.bg.border = data_add.bg.border;
 		}
+		self.photo.append(&mut data_add.photo);
+		self.temp.append(&mut data_add.temp);
+		self.flow.append(&mut data_add.flow);
 		let pthoto_add = self.photo.len() - old_photos;
 		let temp_add = self.temp.len() - old_temps;
 		let flow_add = self.flow.len() - old_flows;
+		if border_replaced {
+			// Old border may hold extremes, recompute from scratch.
+			self.rebound();
+		} else {
+			self.bounds.add_photo(&self.photo, old_photos);
+			self.bounds.add_temp(&self.temp, old_temps);
+			self.bounds.add_flow(&self.flow, old_flows);
+		}
+		self.reindex();
 		(pthoto_add, temp_add, flow_add)
 	}
 
-	pub fn query_2d(&self, q: &Query) -> QueryResult {
+	/// Number of photos, temps and flows outside of border.
+	pub fn outside_border(&self) -> (usize, usize, usize) {
+		(
+			(0..self.photo.len()).filter(|i| !self.bg.contains(&self.photo.base.point(*i))).count(),
+			(0..self.temp.len()).filter(|i| !self.bg.contains(&self.temp.base.point(*i))).count(),
+			(0..self.flow.len()).filter(|i| !self.bg.contains(&self.flow.base.point(*i))).count()
+		)
+	}
+
+	/// Selected records by time and depth index, indices are sorted as in `query_2d_scan`.
+	pub fn query_2d(&self, sel: &Selection) -> QueryResult {
+		let (from, to) = sel.time_window();
+		let query = |index: &ChannelIndex, base: &BaseColumns, channel: Channel| {
+			let (deep_min, deep_max) = sel.deep_window(channel);
+			let mut res: Vec<usize> = index.candidates(from, to, deep_min, deep_max)
+				.filter(|i| sel.contains(base.timestamp[*i], base.deep[*i], channel))
+				.collect();
+			res.sort_unstable();
+			res
+		};
 		QueryResult {
-			photo: {
-				let mut data = Vec::new();
-				for p in &self.photo {
-					if in_delta_i64(p.timestamp, q.timestamp, q.timestamp_d) {
-						if in_delta_f64(p.point.deep, q.deep, q.photo_deep_d) {
-							data.push(p.clone());
-						}
-					}
-				}
-				data
-			},
-			temp: {
-				let mut data = Vec::new();
-				for p in &self.temp {
-					if in_delta_i64(p.timestamp, q.timestamp, q.timestamp_d) {
-						if in_delta_f64(p.point.deep, q.deep, q.temp_deep_d) {
-							data.push(p.clone());
-						}
-					}
-				}
-				data
-			},
-			flow: {
-				let mut data = Vec::new();
-				for p in &self.flow {
-					if in_delta_i64(p.timestamp, q.timestamp, q.timestamp_d) {
-						if in_delta_f64(p.point.deep, q.deep, q.flow_deep_d) {
-							data.push(p.clone());
-						}
-					}
-				}
-				data
-			}
+			photo: query(&self.index.photo, &self.photo.base, Channel::Photo),
+			temp: query(&self.index.temp, &self.temp.base, Channel::Temp),
+			flow: query(&self.index.flow, &self.flow.base, Channel::Flow)
 		}
 	}
 
-	pub fn ranges(&self) -> Ranges {
-		let mut dmax = f64::MIN;
-		let mut dmin = f64::MAX;
-		let mut tsmax = i64::MIN;
-		let mut tsmin = i64::MAX;
-		let mut is_deep_exists = false;
-		let mut is_timestamp_exists = false;
-		for p in &self.bg.border {
-			is_deep_exists = true;
-			if p.deep > dmax {dmax = p.deep}
-			if p.deep < dmin {dmin = p.deep}
-		}
-		for p in &self.photo {
-			is_deep_exists = true;
-			is_timestamp_exists = true;
-			if p.point.deep > dmax {dmax = p.point.deep}
-			if p.point.deep < dmin {dmin = p.point.deep}
-			if p.timestamp > tsmax {tsmax = p.timestamp}
-			if p.timestamp < tsmin {tsmin = p.timestamp}
-		}
-		for p in &self.temp {
-			is_deep_exists = true;
-			is_timestamp_exists = true;
-			if p.point.deep > dmax {dmax = p.point.deep}
-			if p.point.deep < dmin {dmin = p.point.deep}
-			if p.timestamp > tsmax {tsmax = p.timestamp}
-			if p.timestamp < tsmin {tsmin = p.timestamp}
-		}
-		for p in &self.flow {
-			is_deep_exists = true;
-			is_timestamp_exists = true;
-			if p.point.deep > dmax {dmax = p.point.deep}
-			if p.point.deep < dmin {dmin = p.point.deep}
-			if p.timestamp > tsmax {tsmax = p.timestamp}
-			if p.timestamp < tsmin {tsmin = p.timestamp}
-		}
-		if !is_deep_exists {
-			dmax = 0.0;
-			dmin = 0.0;
-		}
-		if !is_timestamp_exists {
-			tsmax = 0;
-			tsmin = 0;
-		}
-		Ranges {
-			deep_max: dmax,
-			deep_min: dmin,
-			timestamp_max: tsmax,
-			timestamp_min: tsmin
+	/// Linear scan without index, reference for `query_2d`.
+	pub fn query_2d_scan(&self, sel: &Selection) -> QueryResult {
+		QueryResult {
+			photo: (0..self.photo.len()).filter(|i| sel.contains(self.photo.base.timestamp[*i], self.photo.base.deep[*i], Channel::Photo)).collect(),
+			temp: (0..self.temp.len()).filter(|i| sel.contains(self.temp.base.timestamp[*i], self.temp.base.deep[*i], Channel::Temp)).collect(),
+			flow: (0..self.flow.len()).filter(|i| sel.contains(self.flow.base.timestamp[*i], self.flow.base.deep[*i], Channel::Flow)).collect()
 		}
 	}
+
+	/// Sorted depth levels of channel, depths closer than `tolerance` to previous one
+	/// join its level, level is mean of joined depths.
+	pub fn deep_levels(&self, channel: Channel, tolerance: f64) -> Vec<f64> {
+		let mut deeps: Vec<f64> = match channel {
+			Channel::Photo => self.photo.base.deep.clone(),
+			Channel::Temp => self.temp.base.deep.clone(),
+			Channel::Flow => self.flow.base.deep.clone()
+		};
+		deeps.sort_by(|a, b| a.total_cmp(b));
+		cluster_levels(&deeps, tolerance)
+	}
+
+	/// Sorted distinct timestamps of all records.
+	pub fn timestamps(&self) -> Vec<i64> {
+		let mut ts: Vec<i64> = self.photo.base.timestamp.iter()
+			.chain(self.temp.base.timestamp.iter())
+			.chain(self.flow.base.timestamp.iter())
+			.copied()
+			.collect();
+		ts.sort_unstable();
+		ts.dedup();
+		ts
+	}
+
+	/// Ranges kept up to date by `add` and `clear`.
+	pub fn ranges(&self) -> Ranges {
+		self.bounds.ranges()
+	}
 }
 
 
-pub fn load_data(path: &PathBuf) -> Result<Data, String> {
-	println!("Load data from {:?}", path);
-	let extension = match path.extension() {
-		Some(name) => {
-			let name = name.to_str().unwrap();
-			if name == "xlsx" {
-				Extension::Xlsx
-			} else
-			if name == "dat" {
-				Extension::Msgpack
-			} else {
-				return Err(format!("Extension '{}' of selected file not supported, supported list: [dat, xlsx]", name));
-			}
-		},
-		None => Extension::Msgpack
-	};
-	match extension {
+/// Mean values of runs in sorted `vals` where neighbours differ less than `tolerance`.
+pub fn cluster_levels(vals: &[f64], tolerance: f64) -> Vec<f64> {
+	let mut levels = Vec::new();
+	let mut sum = 0.0;
+	let mut n = 0;
+	let mut prev = f64::NAN;
+	for v in vals {
+		if n > 0 && v - prev > tolerance {
+			levels.push(sum / n as f64);
+			sum = 0.0;
+			n = 0;
+		}
+		sum += v;
+		n += 1;
+		prev = *v;
+	}
+	if n > 0 {
+		levels.push(sum / n as f64);
+	}
+	levels
+}
+
+/// Load data from file, rows of every sheet are counted in `progress`.
+/// In lenient mode bad rows are skipped and returned as problems.
+pub fn load_data(path: &PathBuf, options: &ImportOptions, progress: &Progress) -> Result<(Data, Vec<DataError>), DataError> {
+	match extension(path)? {
 		Extension::Msgpack => {
 			let file = match File::open(path) {
 				Ok(f) => f,
-				Err(e) => return Err(format!("Fail to open dat file: {}", e.to_string()))
+				Err(e) => return Err(DataError::Io(path.clone(), e))
 			};
-			match rmps::decode::from_read(file) {
-				Ok(data) => Ok(data),
-				Err(e) => Err(format!("Decode error: {}", e.to_string()))
-			}
+			progress.stage("Decode", 0);
+			let file: DataFile = rmps::decode::from_read(file)?;
+			progress.check()?;
+			progress.stage("Index", 0);
+			Ok((Data::from(file), Vec::new()))
 		},
 		Extension::Xlsx => {
-			let mut excel: Xlsx<_> = match open_workbook(path) {
-				Ok(data) => data,
-				Err(e) => return Err(format!("Fail to open xlsx file: {}", e.to_string()))
+			let mut excel: Xlsx<_> = open_workbook(path)?;
+			let sheets = &options.profile.sheets;
+			let names = excel.sheet_names().to_owned();
+			let missing = |name: &String| !name.is_empty() && !names.contains(name);
+			if missing(&sheets.photo) || missing(&sheets.temp) || missing(&sheets.flow) {
+				return Err(DataError::UnmatchedSheets(names));
+			}
+			let profile = &options.profile;
+			let bgs = xlsx_open_sheet(&mut excel, &sheets.bg, None)?;
+			let channels = ChannelSheets {
+				border: None,
+				photo: xlsx_open_sheet(&mut excel, &sheets.photo, Some(profile))?,
+				temp: xlsx_open_sheet(&mut excel, &sheets.temp, Some(profile))?,
+				flow: xlsx_open_sheet(&mut excel, &sheets.flow, Some(profile))?
 			};
-			let bgs = xlsx_open_sheet(&mut excel, XLSX_SHEET_BG)?;
-			let photos = xlsx_open_sheet(&mut excel, XLSX_SHEET_PHOTO)?;
-			let temps = xlsx_open_sheet(&mut excel, XLSX_SHEET_TEMP)?;
-			let flows = xlsx_open_sheet(&mut excel, XLSX_SHEET_FLOW)?;
-
-			let bg = match xlsx_load_bg(&bgs) {
-				Ok(data) => data,
-				Err(e) => return Err(format!("Fail to load bg: {}", e))
+
+			let mut rows = RowCollector {
+				mode: options.mode,
+				problems: Vec::new()
 			};
-			let photo = match xlsx_load_photo(&photos) {
-				Ok(data) => data,
-				Err(e) => return Err(format!("Fail to load photo: {}", e))
+			let mut bg = match &bgs {
+				Some(sheet) => {
+					progress.stage(&sheet.name, sheet.range.height().saturating_sub(5));
+					xlsx_load_bg(sheet, &mut rows, progress)?
+				},
+				None => Background::default()
 			};
-			let temp = match xlsx_load_temp(&temps) {
-				Ok(data) => data,
-				Err(e) => return Err(format!("Fail to load temp: {}", e))
+			if let Some(image) = bg.image.as_mut() {
+				image.image_path = resolve_path(path, &image.image_path);
+			}
+			let mut file = load_channels(&channels, &mut rows, progress)?;
+			file.bg = bg;
+
+			progress.stage("Index", 0);
+			Ok((Data::from(file), rows.problems))
+		},
+		Extension::Csv(delimiter) => {
+			let file = match File::open(path) {
+				Ok(f) => f,
+				Err(e) => return Err(DataError::Io(path.clone(), e))
 			};
-			let flow = match xlsx_load_flow(&flows) {
-				Ok(data) => data,
-				Err(e) => return Err(format!("Fail to load flow: {}", e))
+			progress.stage("Read", 0);
+			let mut reader = csv::ReaderBuilder::new()
+				.delimiter(delimiter)
+				.has_headers(false)
+				.flexible(true)
+				.from_reader(file);
+			let mut records = Vec::new();
+			for rec in reader.records() {
+				records.push(rec?);
+				progress.check()?;
+			}
+			let channels = csv_split_channels(path, &records, options)?;
+			let mut rows = RowCollector {
+				mode: options.mode,
+				problems: Vec::new()
 			};
+			let file = load_channels(&channels, &mut rows, progress)?;
+
+			progress.stage("Index", 0);
+			Ok((Data::from(file), rows.problems))
+		}
+	}
+}
+
+/// Sheets of channels found in file, border only in long CSV.
+#[derive(Default)]
+struct ChannelSheets {
+	border: Option<Sheet>,
+	photo: Option<Sheet>,
+	temp: Option<Sheet>,
+	flow: Option<Sheet>
+}
+
+/// Records of present channel sheets, background has border only.
+fn load_channels(channels: &ChannelSheets, rows: &mut RowCollector, progress: &Progress) -> Result<DataFile, DataError> {
+	let border = match &channels.border {
+		Some(sheet) => {
+			progress.stage(&sheet.name, sheet.range.height().saturating_sub(1));
+			csv_load_border(sheet, rows, progress)?
+		},
+		None => Vec::new()
+	};
+	let photo = match &channels.photo {
+		Some(sheet) => {
+			progress.stage(&sheet.name, sheet.range.height().saturating_sub(1));
+			xlsx_load_photo(sheet, rows, progress)?
+		},
+		None => Vec::new()
+	};
+	let temp = match &channels.temp {
+		Some(sheet) => {
+			progress.stage(&sheet.name, sheet.range.height().saturating_sub(1));
+			xlsx_load_temp(sheet, rows, progress)?
+		},
+		None => Vec::new()
+	};
+	let flow = match &channels.flow {
+		Some(sheet) => {
+			progress.stage(&sheet.name, sheet.range.height().saturating_sub(1));
+			xlsx_load_flow(sheet, rows, progress)?
+		},
+		None => Vec::new()
+	};
+	Ok(DataFile {
+		bg: Background {
+			image: None,
+			border: border
+		},
+		photo: photo,
+		temp: temp,
+		flow: flow
+	})
+}
 
-			Ok(Data {
-				bg: bg,
-				photo: photo,
-				temp: temp,
-				flow: flow
+/// Sheets of border, photo, temp and flow from CSV records. File with channel
+/// column is split by `border` type and channel sheet names, otherwise whole
+/// file is the channel of options or the one whose sheet name is in file name.
+fn csv_split_channels(path: &PathBuf, records: &[csv::StringRecord], options: &ImportOptions) -> Result<ChannelSheets, DataError> {
+	let profile = &options.profile;
+	let file_name = match path.file_name() {
+		Some(name) => name.to_string_lossy().to_string(),
+		None => String::new()
+	};
+	let header: Vec<String> = match records.first() {
+		Some(rec) => rec.iter().map(|c| String::from(c)).collect(),
+		None => return Ok(ChannelSheets::default())
+	};
+	let columns = ColumnMap::from_header(&header, &profile.columns);
+	let sheets = &profile.sheets;
+	let same = |a: &str, b: &str| !b.is_empty() && a.trim().to_lowercase() == b.trim().to_lowercase();
+	match columns.channel {
+		Some(c) => {
+			let mut types: Vec<String> = Vec::new();
+			for rec in &records[1..] {
+				let t = rec.get(c).unwrap_or("").trim();
+				if !t.is_empty() && !same(t, CSV_TYPE_BORDER) && !types.iter().any(|n| n == t) {
+					types.push(String::from(t));
+				}
+			}
+			let found = |name: &String| types.iter().any(|t| same(t, name));
+			if !found(&sheets.photo) && !found(&sheets.temp) && !found(&sheets.flow) {
+				return Err(DataError::UnmatchedSheets(types));
+			}
+			let sheet = |name: &str| {
+				let mut rows = records[1..].iter().filter(|rec| same(rec.get(c).unwrap_or(""), name)).peekable();
+				rows.peek()?;
+				Some(csv_sheet(&file_name, &records[0], rows, columns, profile))
+			};
+			Ok(ChannelSheets {
+				border: sheet(CSV_TYPE_BORDER),
+				photo: sheet(&sheets.photo),
+				temp: sheet(&sheets.temp),
+				flow: sheet(&sheets.flow)
 			})
+		},
+		None => {
+			let stem = match path.file_stem() {
+				Some(stem) => stem.to_string_lossy().to_lowercase(),
+				None => String::new()
+			};
+			let named = |name: &String| !name.is_empty() && stem.contains(&name.trim().to_lowercase());
+			let channel = match options.channel {
+				Some(channel) => channel,
+				None if named(&sheets.photo) => Channel::Photo,
+				None if named(&sheets.temp) => Channel::Temp,
+				None if named(&sheets.flow) => Channel::Flow,
+				None => return Err(DataError::UnknownChannel(file_name))
+			};
+			let sheet = Some(csv_sheet(&file_name, &records[0], records[1..].iter(), columns, profile));
+			let mut channels = ChannelSheets::default();
+			match channel {
+				Channel::Photo => channels.photo = sheet,
+				Channel::Temp => channels.temp = sheet,
+				Channel::Flow => channels.flow = sheet
+			}
+			Ok(channels)
 		}
 	}
 }
 
-fn xlsx_load_bg(bgs: &calamine::Range<calamine::DataType>) -> Result<Background, String> {
-	Ok(Background {
-		image: xlsx_get_image(&bgs),
-		border: {
-			let mut data = Vec::new();
-			for i in 5..bgs.rows().len() {
-				data.push(xlsx_get_point(&bgs, i)?);
+fn csv_load_border(borders: &Sheet, rows: &mut RowCollector, progress: &Progress) -> Result<Vec<Point>, DataError> {
+	let mut border = Vec::new();
+	for i in 1..borders.range.rows().len() {
+		progress.step()?;
+		rows.collect(&mut border, xlsx_get_point(borders, i))?;
+	}
+	Ok(border)
+}
+
+/// Header and `rows` as sheet, numbers are parsed to floats.
+fn csv_sheet<'a>(name: &str, header: &'a csv::StringRecord, rows: impl Iterator<Item = &'a csv::StringRecord>, columns: ColumnMap, profile: &ImportProfile) -> Sheet {
+	let records: Vec<&csv::StringRecord> = std::iter::once(header).chain(rows).collect();
+	let width = records.iter().map(|r| r.len()).max().unwrap_or(0).max(1);
+	let mut range = calamine::Range::new((0, 0), (records.len() as u32 - 1, width as u32 - 1));
+	let mut file_rows = Vec::new();
+	for (r, rec) in records.iter().enumerate() {
+		for (c, cell) in rec.iter().enumerate() {
+			let cell = cell.trim();
+			let val = if cell.is_empty() {
+				calamine::DataType::Empty
+			} else {
+				match cell.parse::<f64>() {
+					Ok(v) => calamine::DataType::Float(v),
+					Err(_) => calamine::DataType::String(String::from(cell))
+				}
+			};
+			range.set_value((r as u32, c as u32), val);
+		}
+		file_rows.push(match rec.position() {
+			Some(pos) => pos.line() as usize - 1,
+			None => r
+		});
+	}
+	Sheet {
+		name: String::from(name),
+		range: range,
+		columns: columns,
+		timezone: profile.timezone,
+		utc_offset: profile.utc_offset,
+		numeric_time: profile.numeric_time,
+		file_rows: file_rows
+	}
+}
+
+/// Worksheet with its name for error locations.
+struct Sheet {
+	name: String,
+	range: calamine::Range<calamine::DataType>,
+	columns: ColumnMap,
+	timezone: SourceTimezone,
+	utc_offset: i32,
+	numeric_time: NumericTime,
+	/// Row in file of every sheet row if sheet is part of file.
+	file_rows: Vec<usize>
+}
+
+impl Sheet {
+	fn bad_cell(&self, r: usize, c: usize, expected: &'static str) -> DataError {
+		DataError::BadCell {
+			sheet: self.name.clone(),
+			row: match self.file_rows.get(r) {
+				Some(row) => *row,
+				None => r
+			},
+			col: c,
+			expected: expected,
+			found: match xlsx_cell(self, r, c) {
+				Some(calamine::DataType::Empty) | None => String::from("empty cell"),
+				Some(val) => format!("'{}'", val)
 			}
-			data
 		}
+	}
+}
+
+/// Rows parsed so far, keeps problems of skipped rows in lenient mode.
+struct RowCollector {
+	mode: ImportMode,
+	problems: Vec<DataError>
+}
+
+impl RowCollector {
+	fn collect<T>(&mut self, out: &mut Vec<T>, row: Result<T, DataError>) -> Result<(), DataError> {
+		match row {
+			Ok(val) => out.push(val),
+			Err(e) => match self.mode {
+				ImportMode::Strict => return Err(e),
+				ImportMode::Lenient => self.problems.push(e)
+			}
+		}
+		Ok(())
+	}
+}
+
+fn xlsx_load_bg(bgs: &Sheet, rows: &mut RowCollector, progress: &Progress) -> Result<Background, DataError> {
+	let mut border = Vec::new();
+	for i in 5..bgs.range.rows().len() {
+		progress.step()?;
+		rows.collect(&mut border, xlsx_get_point(&bgs, i))?;
+	}
+	Ok(Background {
+		image: xlsx_get_image(&bgs),
+		border: border
 	})
 }
 
-fn xlsx_load_photo(photos: &calamine::Range<calamine::DataType>) -> Result<Vec<Photo>, String> {
+fn xlsx_load_photo(photos: &Sheet, rows: &mut RowCollector, progress: &Progress) -> Result<Vec<Photo>, DataError> {
 	let mut photo = Vec::new();
-	for i in 1..photos.rows().len() {
-		photo.push(Photo {
-			point: xlsx_get_point(&photos, i)?,
-			timestamp: xlsx_get_timestamp(&photos, i, XLSX_DATETIME_INDEX)?,
-			solar: xlsx_get_f64(&photos, i, XLSX_SPEC_INDEX)?,
-			transparency: {
-				let mut data = Vec::new();
-				let mut pos = XLSX_SPEC_INDEX + 1;
-				let ncells = xlsx_row_len(photos, i);
-				println!("parse photo row {}, ncells: {}", i, ncells);
-				loop {
-					let wl = xlsx_get_f64(&photos, i, pos)?;
-					let val = xlsx_get_f64(&photos, i, pos+1)?;
-					println!("photoval at row {}: ({}, {})", i, wl, val);
-					data.push((wl, val));
-					pos += 2;
-					if pos >= ncells {
-						break;
-					}
-				}
-				data
-			}
-		});
+	for i in 1..photos.range.rows().len() {
+		progress.step()?;
+		rows.collect(&mut photo, xlsx_get_photo(photos, i))?;
 	}
 	Ok(photo)
 }
 
-fn xlsx_load_temp(temps: &calamine::Range<calamine::DataType>) -> Result<Vec<Temp>, String> {
+fn xlsx_get_photo(photos: &Sheet, i: usize) -> Result<Photo, DataError> {
+	Ok(Photo {
+		point: xlsx_get_point(&photos, i)?,
+		timestamp: xlsx_get_timestamp(&photos, i, photos.columns.datetime)?,
+		solar: xlsx_get_f64(&photos, i, photos.columns.solar)?,
+		transparency: {
+			let mut data = Vec::new();
+			let mut pos = photos.columns.spectrum;
+			while !xlsx_is_empty(photos, i, pos) {
+				let wl = xlsx_get_f64(&photos, i, pos)?;
+				let val = xlsx_get_f64(&photos, i, pos+1)?;
+				data.push((wl, val));
+				pos += 2;
+			}
+			data
+		}
+	})
+}
+
+fn xlsx_load_temp(temps: &Sheet, rows: &mut RowCollector, progress: &Progress) -> Result<Vec<Temp>, DataError> {
 	let mut temp = Vec::new();
-	for i in 1..temps.rows().len() {
-		temp.push(Temp {
-			point: xlsx_get_point(&temps, i)?,
-			timestamp: xlsx_get_timestamp(&temps, i, XLSX_DATETIME_INDEX)?,
-			val: xlsx_get_f64(&temps, i, XLSX_SPEC_INDEX)?
-		});
+	for i in 1..temps.range.rows().len() {
+		progress.step()?;
+		rows.collect(&mut temp, xlsx_get_temp(temps, i))?;
 	}
 	Ok(temp)
 }
 
-fn xlsx_load_flow(flows: &calamine::Range<calamine::DataType>) -> Result<Vec<Flow>, String> {
+fn xlsx_get_temp(temps: &Sheet, i: usize) -> Result<Temp, DataError> {
+	Ok(Temp {
+		point: xlsx_get_point(&temps, i)?,
+		timestamp: xlsx_get_timestamp(&temps, i, temps.columns.datetime)?,
+		val: xlsx_get_f64(&temps, i, temps.columns.temp)?
+	})
+}
+
+fn xlsx_load_flow(flows: &Sheet, rows: &mut RowCollector, progress: &Progress) -> Result<Vec<Flow>, DataError> {
 	let mut flow = Vec::new();
-	for i in 1..flows.rows().len() {
-		flow.push(Flow {
-			point: xlsx_get_point(&flows, i)?,
-			timestamp: xlsx_get_timestamp(&flows, i, XLSX_DATETIME_INDEX)?,
-			speed: xlsx_get_f64(&flows, i, XLSX_SPEC_INDEX)?,
-			dir: xlsx_get_f64(&flows, i, XLSX_SPEC_INDEX+1)?
-		});
+	for i in 1..flows.range.rows().len() {
+		progress.step()?;
+		rows.collect(&mut flow, xlsx_get_flow(flows, i))?;
 	}
 	Ok(flow)
 }
 
-fn xlsx_get_point(sheet: &calamine::Range<calamine::DataType>, r: usize) -> Result<Point, String> {
+fn xlsx_get_flow(flows: &Sheet, i: usize) -> Result<Flow, DataError> {
+	Ok(Flow {
+		point: xlsx_get_point(&flows, i)?,
+		timestamp: xlsx_get_timestamp(&flows, i, flows.columns.datetime)?,
+		speed: xlsx_get_f64(&flows, i, flows.columns.speed)?,
+		dir: xlsx_get_f64(&flows, i, flows.columns.dir)?
+	})
+}
+
+fn xlsx_get_point(sheet: &Sheet, r: usize) -> Result<Point, DataError> {
 	Ok(Point {
-		latitude: xlsx_get_f64(sheet, r, XLSX_LATITUDE_INDEX)?,
-		longitude: xlsx_get_f64(sheet, r, XLSX_LONGITUDE_INDEX)?,
-		deep: xlsx_get_f64(sheet, r, XLSX_DEEP_INDEX)?,
+		latitude: xlsx_get_f64(sheet, r, sheet.columns.latitude)?,
+		longitude: xlsx_get_f64(sheet, r, sheet.columns.longitude)?,
+		deep: xlsx_get_f64(sheet, r, sheet.columns.deep)?,
 	})
 }
 
-fn xlsx_get_image(sheet: &calamine::Range<calamine::DataType>) -> Option<BackgroundImage>{
+fn xlsx_get_image(sheet: &Sheet) -> Option<BackgroundImage>{
 	match xlsx_get_str(sheet, 1, 0) {
 		Ok(path) => match xlsx_get_f64(sheet, 1, 1) {
 			Ok(scale) => match xlsx_get_f64(sheet, 1, 2) {
 				Ok(rotate) => Some(BackgroundImage {
 					image_path: path,
 					scale: scale,
-					rotate: rotate
+					rotate: rotate,
+					anchor: match (xlsx_get_f64(sheet, 1, 3), xlsx_get_f64(sheet, 1, 4)) {
+						(Ok(latitude), Ok(longitude)) => Some(Point {
+							latitude: latitude,
+							longitude: longitude,
+							deep: 0.0
+						}),
+						_ => None
+					}
 				}),
 				_ => None
 			},
@@ -410,63 +972,100 @@ fn xlsx_get_image(sheet: &calamine::Range<calamine::DataType>) -> Option<Backgro
 	}
 }
 
-fn xlsx_get_f64(sheet: &calamine::Range<calamine::DataType>, r: usize, c: usize) -> Result<f64, String> {
-	match sheet.get((r, c)) {
-		Some(val) => match val.get_float() {
-			Some(val) => Ok(val),
-			None => Err(format!("Fail to get value from ({}, {}) - expected float, found '{:?}'", r, c, val))
-		},
-		None => Err(format!("Fail to get value from ({}, {}) - none", r, c))
+/// Path relative to directory of source file, absolute paths kept as is.
+fn resolve_path(source: &PathBuf, path: &str) -> String {
+	let p = PathBuf::from(path);
+	if p.is_absolute() {
+		return String::from(path);
+	}
+	let source = source.canonicalize().unwrap_or(source.clone());
+	match source.parent() {
+		Some(dir) => dir.join(p).to_string_lossy().to_string(),
+		None => String::from(path)
 	}
 }
 
-fn xlsx_get_str(sheet: &calamine::Range<calamine::DataType>, r: usize, c: usize) -> Result<String, String> {
-	match sheet.get((r, c)) {
-		Some(val) => match val.get_string() {
-			Some(val) => Ok(String::from(val)),
-			None => Err(format!("Fail to get value from ({}, {}) - expected string, found '{:?}'", r, c, val))
-		},
-		None => Err(format!("Fail to get value from ({}, {}) - none", r, c))
+/// Cell of sheet, none past last column, as `Range::get` would read next row there.
+fn xlsx_cell(sheet: &Sheet, r: usize, c: usize) -> Option<&calamine::DataType> {
+	if c >= sheet.range.width() {
+		return None;
 	}
+	sheet.range.get((r, c))
 }
 
-fn xlsx_get_timestamp(sheet: &calamine::Range<calamine::DataType>, r: usize, c: usize) -> Result<i64, String> {
-	match sheet.get((r, c)) {
-		Some(val) => match val {
-			calamine::DataType::DateTime(exldt) => Ok(((exldt - 25569.0) * 86400.0) as i64),
-			oth => Err(format!("Fail to get value from ({}, {}) - expected string, found '{:?}'", r, c, oth))
-		},
-		None => Err(format!("Fail to get value from ({}, {}) - none", r, c))
+fn xlsx_get_f64(sheet: &Sheet, r: usize, c: usize) -> Result<f64, DataError> {
+	match xlsx_cell(sheet, r, c).and_then(|val| val.get_float()) {
+		Some(val) => Ok(val),
+		None => Err(sheet.bad_cell(r, c, "float"))
 	}
 }
 
-fn xlsx_open_sheet(excel: &mut Xlsx<BufReader<File>>, name: &str) -> Result<calamine::Range<calamine::DataType>, String> {
-	match excel.worksheet_range(name) {
-		Some(res) => match res {
-			Ok(data) => Ok(data),
-			Err(e) => Err(format!("Fail to read '{}': {}", name, e.to_string()))
-		},
-		None => Err(format!("Sheet '{}' not found in book", name))
+fn xlsx_get_str(sheet: &Sheet, r: usize, c: usize) -> Result<String, DataError> {
+	match xlsx_cell(sheet, r, c).and_then(|val| val.get_string()) {
+		Some(val) => Ok(String::from(val)),
+		None => Err(sheet.bad_cell(r, c, "string"))
 	}
 }
 
-fn xlsx_row_len(sheet: &calamine::Range<calamine::DataType>, r: usize) -> usize {
-	let ncells = sheet.cells().len();
-	let mut cnt = 0;
-	for i in 0..ncells {
-		let val = match sheet.get((r, i)) {
-			Some(val) => val,
-			None => return 0
-		};
-		match val {
-			calamine::DataType::Empty => break,
-			_ => cnt += 1
-		}
+/// Excel date and datetime strings are in source timezone, numbers are Unix seconds.
+fn xlsx_get_timestamp(sheet: &Sheet, r: usize, c: usize) -> Result<i64, DataError> {
+	let ts = match xlsx_cell(sheet, r, c) {
+		Some(calamine::DataType::DateTime(serial)) => import::excel_to_naive(*serial)
+			.and_then(|dt| import::local_to_timestamp(&dt, sheet.timezone, sheet.utc_offset)),
+		Some(calamine::DataType::String(s)) => import::parse_datetime(s, sheet.timezone, sheet.utc_offset),
+		Some(calamine::DataType::Float(v)) => import::numeric_to_timestamp(*v, sheet.numeric_time, sheet.timezone, sheet.utc_offset),
+		Some(calamine::DataType::Int(v)) => import::numeric_to_timestamp(*v as f64, sheet.numeric_time, sheet.timezone, sheet.utc_offset),
+		_ => None
+	};
+	match (ts, sheet.numeric_time) {
+		(Some(ts), _) => Ok(ts),
+		(None, NumericTime::Unix) => Err(sheet.bad_cell(r, c, "datetime, ISO-8601 string or Unix seconds")),
+		(None, NumericTime::Excel) => Err(sheet.bad_cell(r, c, "datetime, ISO-8601 string or Excel serial date"))
+	}
+}
+
+/// Open sheet, none if name is empty or not in book.
+/// Columns are mapped by header row and times converted by `profile` if given.
+fn xlsx_open_sheet(excel: &mut Xlsx<BufReader<File>>, name: &str, profile: Option<&ImportProfile>) -> Result<Option<Sheet>, DataError> {
+	if name.is_empty() {
+		return Ok(None);
 	}
-	cnt
+	let range = match excel.worksheet_range(name) {
+		Some(res) => res?,
+		None => return Ok(None)
+	};
+	let columns = match (profile, range.rows().next()) {
+		(Some(profile), Some(header)) => {
+			let header: Vec<String> = header.iter().map(|c| c.to_string()).collect();
+			ColumnMap::from_header(&header, &profile.columns)
+		},
+		_ => ColumnMap::default()
+	};
+	Ok(Some(Sheet {
+		name: String::from(name),
+		range: range,
+		columns: columns,
+		timezone: profile.map(|p| p.timezone).unwrap_or_default(),
+		utc_offset: profile.map(|p| p.utc_offset).unwrap_or(0),
+		numeric_time: profile.map(|p| p.numeric_time).unwrap_or_default(),
+		file_rows: Vec::new()
+	}))
 }
 
-pub fn save_data(path: &PathBuf, data: &Data) -> Result<(), String> {
+fn xlsx_is_empty(sheet: &Sheet, r: usize, c: usize) -> bool {
+	match xlsx_cell(sheet, r, c) {
+		Some(calamine::DataType::Empty) | None => true,
+		_ => false
+	}
+}
+
+/// Warnings about parts of data not written on success.
+pub fn save_data(path: &PathBuf, data: &DataFile) -> Result<Vec<String>, DataError> {
+	let delimiter = match extension(path)? {
+		Extension::Msgpack => None,
+		Extension::Csv(delimiter) => Some(delimiter),
+		Extension::Xlsx => return Err(DataError::UnsupportedExtension(String::from("xlsx")))
+	};
 	let mut open_opt = OpenOptions::new();
 	open_opt.append(false);
 	open_opt.write(true);
@@ -475,9 +1074,342 @@ pub fn save_data(path: &PathBuf, data: &Data) -> Result<(), String> {
 	open_opt.create_new(false);
 	let mut file = match open_opt.open(path) {
 		Ok(f) => f,
-		Err(e) => return Err(format!("Fail open file to write: {}", e.to_string()))
+		Err(e) => return Err(DataError::Io(path.clone(), e))
 	};
-	rmps::encode::write(&mut file, data).unwrap();
-	println!("Data saved at {:?}", path);
-	Ok(())
-}
\ No newline at end of file
+	let warnings = match delimiter {
+		Some(delimiter) => save_csv(file, data, delimiter)?,
+		None => {
+			rmps::encode::write(&mut file, data)?;
+			Vec::new()
+		}
+	};
+	Ok(warnings)
+}
+
+fn csv_timestamp(ts: i64) -> String {
+	match Utc.timestamp_opt(ts, 0).single() {
+		Some(dt) => dt.to_rfc3339_opts(SecondsFormat::Secs, true),
+		None => ts.to_string()
+	}
+}
+
+/// Long format with type column, border as `border` rows, image is not written.
+fn save_csv(file: File, data: &DataFile, delimiter: u8) -> Result<Vec<String>, DataError> {
+	let mut writer = csv::WriterBuilder::new()
+		.delimiter(delimiter)
+		.flexible(true)
+		.from_writer(file);
+	let spectrum = data.photo.iter().map(|p| p.transparency.len()).max().unwrap_or(0);
+	let mut header: Vec<&str> = CSV_HEADER.to_vec();
+	for _ in 0..spectrum {
+		header.extend_from_slice(&CSV_SPECTRUM_HEADER);
+	}
+	writer.write_record(&header)?;
+	let base = |kind: &str, point: &Point, ts: i64| vec![
+		String::from(kind),
+		point.latitude.to_string(),
+		point.longitude.to_string(),
+		point.deep.to_string(),
+		csv_timestamp(ts)
+	];
+	for p in &data.bg.border {
+		writer.write_record(&[CSV_TYPE_BORDER, &p.latitude.to_string(), &p.longitude.to_string(), &p.deep.to_string()])?;
+	}
+	for p in &data.photo {
+		let mut rec = base(XLSX_SHEET_PHOTO, &p.point, p.timestamp);
+		rec.extend([p.solar.to_string(), String::new(), String::new(), String::new()]);
+		for (wl, val) in &p.transparency {
+			rec.push(wl.to_string());
+			rec.push(val.to_string());
+		}
+		writer.write_record(&rec)?;
+	}
+	for t in &data.temp {
+		let mut rec = base(XLSX_SHEET_TEMP, &t.point, t.timestamp);
+		rec.extend([String::new(), t.val.to_string(), String::new(), String::new()]);
+		writer.write_record(&rec)?;
+	}
+	for f in &data.flow {
+		let mut rec = base(XLSX_SHEET_FLOW, &f.point, f.timestamp);
+		rec.extend([String::new(), String::new(), f.speed.to_string(), f.dir.to_string()]);
+		writer.write_record(&rec)?;
+	}
+	if let Err(e) = writer.flush() {
+		return Err(DataError::Csv(e.into()));
+	}
+	let mut warnings = Vec::new();
+	if data.bg.image.is_some() {
+		warnings.push(String::from("Background image is not written to CSV, use .dat to keep it"));
+	}
+	Ok(warnings)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::data_gen;
+	use crate::utils::import::ImportConfig;
+
+	fn tmp_path(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("visio_test_{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		dir.join(name)
+	}
+
+	fn load(path: &PathBuf, options: &ImportOptions) -> Result<(Data, Vec<DataError>), DataError> {
+		load_data(path, options, &Progress::default())
+	}
+
+	fn xyz(p: &Point) -> (f64, f64, f64) {
+		(p.latitude, p.longitude, p.deep)
+	}
+
+	fn assert_same(a: &DataFile, b: &DataFile) {
+		assert_eq!(a.bg.border.iter().map(xyz).collect::<Vec<_>>(), b.bg.border.iter().map(xyz).collect::<Vec<_>>());
+		assert_eq!(a.photo.len(), b.photo.len());
+		for (a, b) in a.photo.iter().zip(&b.photo) {
+			assert_eq!((xyz(&a.point), a.timestamp, a.solar), (xyz(&b.point), b.timestamp, b.solar));
+			assert_eq!(a.transparency, b.transparency);
+		}
+		assert_eq!(a.temp.len(), b.temp.len());
+		for (a, b) in a.temp.iter().zip(&b.temp) {
+			assert_eq!((xyz(&a.point), a.timestamp, a.val), (xyz(&b.point), b.timestamp, b.val));
+		}
+		assert_eq!(a.flow.len(), b.flow.len());
+		for (a, b) in a.flow.iter().zip(&b.flow) {
+			assert_eq!((xyz(&a.point), a.timestamp, a.speed, a.dir), (xyz(&b.point), b.timestamp, b.speed, b.dir));
+		}
+	}
+
+	/// Generated records with border and a photo without spectrum.
+	fn sample() -> DataFile {
+		let mut file = data_gen::generate(50, 3).to_file();
+		file.bg.border = vec![
+			Point {latitude: 55.0, longitude: 37.0, deep: 0.0},
+			Point {latitude: 55.1, longitude: 37.0, deep: 0.0},
+			Point {latitude: 55.1, longitude: 37.1, deep: 0.0}
+		];
+		let mut photo = file.photo[0].clone();
+		photo.transparency.clear();
+		file.photo.push(photo);
+		file
+	}
+
+	#[test]
+	fn long_csv_round_trip() {
+		let file = sample();
+		for name in ["long.csv", "long.tsv"] {
+			let path = tmp_path(name);
+			assert!(save_data(&path, &file).unwrap().is_empty());
+			let (data, problems) = load(&path, &ImportConfig::default().options()).unwrap();
+			assert!(problems.is_empty());
+			assert_same(&file, &data.to_file());
+		}
+	}
+
+	/// Saved long CSV of one channel without type column.
+	fn save_channel(name: &str, file: &DataFile) -> PathBuf {
+		let long = tmp_path(&format!("long_{}", name));
+		save_data(&long, file).unwrap();
+		let mut reader = csv::ReaderBuilder::new().has_headers(false).flexible(true).from_path(&long).unwrap();
+		let path = tmp_path(name);
+		let mut writer = csv::WriterBuilder::new().flexible(true).from_path(&path).unwrap();
+		for rec in reader.records() {
+			let rec = rec.unwrap();
+			if rec.get(0) != Some(CSV_TYPE_BORDER) {
+				writer.write_record(rec.iter().skip(1)).unwrap();
+			}
+		}
+		writer.flush().unwrap();
+		path
+	}
+
+	#[test]
+	fn channel_csv_round_trip() {
+		let mut file = sample();
+		file.bg.border.clear();
+		let photos = DataFile {
+			bg: Background::default(),
+			photo: file.photo.clone(),
+			temp: Vec::new(),
+			flow: Vec::new()
+		};
+		let temps = DataFile {
+			bg: Background::default(),
+			photo: Vec::new(),
+			temp: file.temp.clone(),
+			flow: Vec::new()
+		};
+		let flows = DataFile {
+			bg: Background::default(),
+			photo: Vec::new(),
+			temp: Vec::new(),
+			flow: file.flow.clone()
+		};
+		let options = ImportConfig::default().options();
+		let mut data = Data::default();
+		for (name, part) in [("photo.csv", &photos), ("temp_1.csv", &temps), ("flow.csv", &flows)] {
+			let (loaded, problems) = load(&save_channel(name, part), &options).unwrap();
+			assert!(problems.is_empty());
+			assert_same(part, &loaded.to_file());
+			data.add(loaded);
+		}
+		assert_same(&file, &data.to_file());
+	}
+
+	#[test]
+	fn channel_csv_needs_channel() {
+		let path = save_channel("records.csv", &sample());
+		let mut options = ImportConfig::default().options();
+		assert!(matches!(load(&path, &options), Err(DataError::UnknownChannel(name)) if name == "records.csv"));
+		options.channel = Some(Channel::Temp);
+		let (data, _) = load(&save_channel("records.csv", &DataFile {
+			bg: Background::default(),
+			photo: Vec::new(),
+			temp: sample().temp,
+			flow: Vec::new()
+		}), &options).unwrap();
+		assert_eq!(data.temp.len(), 50);
+	}
+
+	#[test]
+	fn long_csv_some_channels() {
+		let mut file = sample();
+		file.photo.clear();
+		let path = tmp_path("some_channels.csv");
+		save_data(&path, &file).unwrap();
+		let (data, problems) = load(&path, &ImportConfig::default().options()).unwrap();
+		assert!(problems.is_empty());
+		assert_eq!((data.photo.len(), data.temp.len(), data.flow.len()), (0, 50, 50));
+		assert_same(&file, &data.to_file());
+	}
+
+	#[test]
+	fn long_csv_no_known_channels() {
+		let long = tmp_path("probes_long.csv");
+		save_data(&long, &sample()).unwrap();
+		let mut reader = csv::ReaderBuilder::new().has_headers(false).flexible(true).from_path(&long).unwrap();
+		let path = tmp_path("probes.csv");
+		let mut writer = csv::WriterBuilder::new().flexible(true).from_path(&path).unwrap();
+		for (i, rec) in reader.records().enumerate() {
+			let rec = rec.unwrap();
+			let kind = match rec.get(0) {
+				Some(CSV_TYPE_BORDER) => CSV_TYPE_BORDER,
+				_ if i == 0 => "type",
+				_ => "probe"
+			};
+			writer.write_record(std::iter::once(kind).chain(rec.iter().skip(1))).unwrap();
+		}
+		writer.flush().unwrap();
+		let res = load(&path, &ImportConfig::default().options());
+		assert!(matches!(res, Err(DataError::UnmatchedSheets(types)) if types == vec![String::from("probe")]));
+	}
+
+	#[test]
+	fn cluster_levels_tolerance() {
+		assert!(cluster_levels(&[], 0.5).is_empty());
+		assert_eq!(cluster_levels(&[2.0], 0.5), vec![2.0]);
+		assert_eq!(cluster_levels(&[1.0, 1.5], 0.5), vec![1.25]);
+		assert_eq!(cluster_levels(&[1.0, 1.75], 0.5), vec![1.0, 1.75]);
+		assert_eq!(cluster_levels(&[0.0, 0.5, 1.0, 3.0, 3.0], 0.5), vec![0.5, 3.0]);
+		assert_eq!(cluster_levels(&[1.0, 2.0], 0.0), vec![1.0, 2.0]);
+	}
+
+	#[test]
+	fn deep_levels_of_unsorted() {
+		let point = |deep: f64| Point {latitude: 55.0, longitude: 37.0, deep: deep};
+		let data = Data::from(DataFile {
+			bg: Background::default(),
+			photo: Vec::new(),
+			temp: [10.0, 0.0, 10.25, 0.25, 5.0].iter().map(|d| Temp {point: point(*d), timestamp: 0, val: 4.0}).collect(),
+			flow: Vec::new()
+		});
+		assert_eq!(data.deep_levels(Channel::Temp, 0.5), vec![0.125, 5.0, 10.125]);
+		assert!(data.deep_levels(Channel::Photo, 0.5).is_empty());
+	}
+
+	/// Temp CSV with unparsable temperature in third row.
+	fn save_bad_temp(name: &str) -> PathBuf {
+		let path = save_channel(name, &DataFile {
+			bg: Background::default(),
+			photo: Vec::new(),
+			temp: sample().temp,
+			flow: Vec::new()
+		});
+		let mut records: Vec<csv::StringRecord> = csv::ReaderBuilder::new().has_headers(false).flexible(true)
+			.from_path(&path).unwrap().records().map(|r| r.unwrap()).collect();
+		let temp = records[0].iter().position(|c| c == "temp").unwrap();
+		records[2] = records[2].iter().enumerate().map(|(i, c)| if i == temp {"warm"} else {c}).collect();
+		let mut writer = csv::WriterBuilder::new().flexible(true).from_path(&path).unwrap();
+		for rec in &records {
+			writer.write_record(rec).unwrap();
+		}
+		writer.flush().unwrap();
+		path
+	}
+
+	#[test]
+	fn bad_row_strict() {
+		let path = save_bad_temp("temp_strict.csv");
+		let options = ImportConfig::default().options();
+		match load(&path, &options) {
+			Err(e @ DataError::BadCell {..}) => assert_eq!(e.location().unwrap(), "temp_strict.csv!F3"),
+			_ => panic!("bad cell expected")
+		}
+	}
+
+	#[test]
+	fn bad_row_lenient() {
+		let path = save_bad_temp("temp_lenient.csv");
+		let mut options = ImportConfig::default().options();
+		options.mode = ImportMode::Lenient;
+		let (data, problems) = load(&path, &options).unwrap();
+		assert_eq!(data.temp.len(), 49);
+		assert_eq!(problems.len(), 1);
+		assert_eq!(problems[0].location().unwrap(), "temp_lenient.csv!F3");
+	}
+
+	#[test]
+	fn empty_spectrum_is_kept() {
+		let file = sample();
+		let path = tmp_path("empty_spectrum.csv");
+		save_data(&path, &file).unwrap();
+		let (data, _) = load(&path, &ImportConfig::default().options()).unwrap();
+		assert!(data.to_file().photo.last().unwrap().transparency.is_empty());
+	}
+
+	#[test]
+	fn index_matches_scan() {
+		let data = data_gen::generate(2000, 7);
+		let ranges = data.ranges();
+		let span = ranges.timestamp_max - ranges.timestamp_min;
+		let mut sels = Vec::new();
+		for i in 0..40 {
+			let t = ranges.timestamp_min + span * i / 39;
+			let deep = (i % 10) as f64 * 5.0;
+			sels.push(Selection::Delta(Query {
+				timestamp: t,
+				timestamp_d: 86_400 * (1 + i % 3),
+				deep,
+				temp_deep_d: 1.0,
+				photo_deep_d: 0.5,
+				flow_deep_d: 2.0
+			}));
+			sels.push(Selection::Range(RangeQuery {
+				timestamp_start: ranges.timestamp_min,
+				timestamp_end: t,
+				deep_min: deep,
+				deep_max: deep + 10.0
+			}));
+		}
+		let mut found = 0;
+		for sel in &sels {
+			let (index, scan) = (data.query_2d(sel), data.query_2d_scan(sel));
+			assert_eq!(index.photo, scan.photo);
+			assert_eq!(index.temp, scan.temp);
+			assert_eq!(index.flow, scan.flow);
+			found += index.photo.len() + index.temp.len() + index.flow.len();
+		}
+		assert!(found > 0);
+	}
+}
@@ -0,0 +1,488 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Duration};
+use serde::{Deserialize, Serialize};
+
+use super::data_loader::Channel;
+
+/// Positional layout, used for columns not found in header.
+pub const XLSX_LATITUDE_INDEX: usize = 0;
+pub const XLSX_LONGITUDE_INDEX: usize = 1;
+pub const XLSX_DEEP_INDEX: usize = 2;
+pub const XLSX_DATETIME_INDEX: usize = 3;
+pub const XLSX_SPEC_INDEX: usize = 5;
+
+pub const XLSX_SHEET_BG: &str = "bg";
+pub const XLSX_SHEET_PHOTO: &str = "photo";
+pub const XLSX_SHEET_TEMP: &str = "temp";
+pub const XLSX_SHEET_FLOW: &str = "flow";
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Formats of datetime strings without offset, tried in order.
+const DATETIME_FORMATS: [&str; 6] = [
+	"%Y-%m-%dT%H:%M:%S%.f",
+	"%Y-%m-%d %H:%M:%S%.f",
+	"%Y-%m-%d %H:%M",
+	"%d.%m.%Y %H:%M:%S",
+	"%d.%m.%Y %H:%M",
+	"%Y/%m/%d %H:%M:%S"
+];
+const DATE_FORMATS: [&str; 3] = ["%Y-%m-%d", "%d.%m.%Y", "%Y/%m/%d"];
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+	/// Reject file on first bad cell.
+	Strict,
+	/// Skip bad rows and report them.
+	Lenient
+}
+
+/// Channels of per channel files with names.
+pub const CHANNELS: [(Channel, &str); 3] = [(Channel::Photo, "Photo"), (Channel::Temp, "Temp"), (Channel::Flow, "Flow")];
+
+/// Timezone of datetimes without offset in imported file.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceTimezone {
+	Utc,
+	Local,
+	/// Fixed `utc_offset` of profile.
+	Offset
+}
+
+impl Default for SourceTimezone {
+	fn default() -> Self {
+		Self::Utc
+	}
+}
+
+/// Meaning of plain numbers in datetime column.
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum NumericTime {
+	/// Unix seconds, values below `MIN_UNIX_SECONDS` are rejected.
+	Unix,
+	/// Excel serial date in source timezone.
+	Excel
+}
+
+impl Default for NumericTime {
+	fn default() -> Self {
+		Self::Unix
+	}
+}
+
+/// About 11 days after epoch, smaller numbers are likely Excel dates without date format.
+pub const MIN_UNIX_SECONDS: f64 = 1e6;
+
+/// Timestamp of wall time in source timezone, `offset` in minutes east of UTC.
+pub fn local_to_timestamp(dt: &NaiveDateTime, tz: SourceTimezone, offset: i32) -> Option<i64> {
+	match tz {
+		SourceTimezone::Utc => Some(Utc.from_utc_datetime(dt).timestamp()),
+		SourceTimezone::Local => Local.from_local_datetime(dt).earliest().map(|dt| dt.timestamp()),
+		SourceTimezone::Offset => FixedOffset::east_opt(offset * 60)
+			.and_then(|fo| fo.from_local_datetime(dt).single())
+			.map(|dt| dt.timestamp())
+	}
+}
+
+/// Wall time of Excel serial date, days since 1899-12-30.
+pub fn excel_to_naive(serial: f64) -> Option<NaiveDateTime> {
+	let epoch = NaiveDate::from_ymd_opt(1899, 12, 30)?.and_hms_opt(0, 0, 0)?;
+	epoch.checked_add_signed(Duration::milliseconds((serial * 86_400_000.0).round() as i64))
+}
+
+/// Timestamp of number in datetime column.
+pub fn numeric_to_timestamp(v: f64, numeric: NumericTime, tz: SourceTimezone, offset: i32) -> Option<i64> {
+	match numeric {
+		NumericTime::Unix if v.abs() < MIN_UNIX_SECONDS => None,
+		NumericTime::Unix => Some(v.round() as i64),
+		NumericTime::Excel => excel_to_naive(v).and_then(|dt| local_to_timestamp(&dt, tz, offset))
+	}
+}
+
+/// Timestamp of ISO-8601/RFC 3339 string, common date formats or Unix seconds,
+/// strings without offset are in source timezone.
+pub fn parse_datetime(s: &str, tz: SourceTimezone, offset: i32) -> Option<i64> {
+	let s = s.trim();
+	if let Ok(ts) = s.parse::<i64>() {
+		return Some(ts);
+	}
+	if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+		return Some(dt.timestamp());
+	}
+	for f in DATETIME_FORMATS {
+		if let Ok(dt) = NaiveDateTime::parse_from_str(s, f) {
+			return local_to_timestamp(&dt, tz, offset);
+		}
+	}
+	for f in DATE_FORMATS {
+		if let Ok(d) = NaiveDate::parse_from_str(s, f) {
+			return local_to_timestamp(&d.and_hms_opt(0, 0, 0)?, tz, offset);
+		}
+	}
+	None
+}
+
+/// Header names of columns, compared case insensitive.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ColumnAliases {
+	pub latitude: Vec<String>,
+	pub longitude: Vec<String>,
+	pub deep: Vec<String>,
+	pub datetime: Vec<String>,
+	pub solar: Vec<String>,
+	/// First wavelength column, wavelength and value pairs follow to end of row.
+	pub spectrum: Vec<String>,
+	pub temp: Vec<String>,
+	pub speed: Vec<String>,
+	pub dir: Vec<String>,
+	/// Channel of row in long CSV files, value is sheet name of channel.
+	#[serde(default = "default_channel_aliases")]
+	pub channel: Vec<String>
+}
+
+fn default_channel_aliases() -> Vec<String> {
+	names(&["type", "channel", "тип"])
+}
+
+fn names(list: &[&str]) -> Vec<String> {
+	list.iter().map(|n| String::from(*n)).collect()
+}
+
+impl Default for ColumnAliases {
+	fn default() -> Self {
+		Self {
+			latitude: names(&["lat", "latitude", "широта"]),
+			longitude: names(&["lon", "lng", "longitude", "долгота"]),
+			deep: names(&["deep", "depth", "глубина"]),
+			datetime: names(&["datetime", "time", "date", "время", "дата"]),
+			solar: names(&["solar", "irradiance", "освещенность"]),
+			spectrum: names(&["wavelength", "wl", "spectrum", "длина волны"]),
+			temp: names(&["temp", "temperature", "температура"]),
+			speed: names(&["speed", "velocity", "скорость"]),
+			dir: names(&["dir", "direction", "направление"]),
+			channel: default_channel_aliases()
+		}
+	}
+}
+
+/// Names of `ColumnAliases` fields in order of `fields` and `fields_mut`.
+pub const COLUMN_FIELDS: [&str; 10] = ["Latitude", "Longitude", "Deep", "Datetime", "Solar", "Spectrum", "Temp", "Speed", "Dir", "Channel"];
+
+impl ColumnAliases {
+	pub fn fields(&self) -> [&Vec<String>; 10] {
+		[&self.latitude, &self.longitude, &self.deep, &self.datetime, &self.solar, &self.spectrum, &self.temp, &self.speed, &self.dir, &self.channel]
+	}
+
+	pub fn fields_mut(&mut self) -> [&mut Vec<String>; 10] {
+		[&mut self.latitude, &mut self.longitude, &mut self.deep, &mut self.datetime, &mut self.solar, &mut self.spectrum, &mut self.temp, &mut self.speed, &mut self.dir, &mut self.channel]
+	}
+}
+
+/// Sheet of every channel, empty name skips channel.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct SheetNames {
+	pub bg: String,
+	pub photo: String,
+	pub temp: String,
+	pub flow: String
+}
+
+impl Default for SheetNames {
+	fn default() -> Self {
+		Self {
+			bg: String::from(XLSX_SHEET_BG),
+			photo: String::from(XLSX_SHEET_PHOTO),
+			temp: String::from(XLSX_SHEET_TEMP),
+			flow: String::from(XLSX_SHEET_FLOW)
+		}
+	}
+}
+
+/// Names of `SheetNames` fields in order of `fields` and `fields_mut`.
+pub const SHEET_FIELDS: [&str; 4] = ["Background", "Photo", "Temp", "Flow"];
+
+impl SheetNames {
+	pub fn fields(&self) -> [&String; 4] {
+		[&self.bg, &self.photo, &self.temp, &self.flow]
+	}
+
+	pub fn fields_mut(&mut self) -> [&mut String; 4] {
+		[&mut self.bg, &mut self.photo, &mut self.temp, &mut self.flow]
+	}
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ImportProfile {
+	pub name: String,
+	#[serde(default)]
+	pub sheets: SheetNames,
+	pub columns: ColumnAliases,
+	#[serde(default)]
+	pub timezone: SourceTimezone,
+	/// Minutes east of UTC, used with `SourceTimezone::Offset`.
+	#[serde(default)]
+	pub utc_offset: i32,
+	#[serde(default)]
+	pub numeric_time: NumericTime
+}
+
+impl Default for ImportProfile {
+	fn default() -> Self {
+		Self {
+			name: String::from(DEFAULT_PROFILE),
+			sheets: SheetNames::default(),
+			columns: ColumnAliases::default(),
+			timezone: SourceTimezone::Utc,
+			utc_offset: 0,
+			numeric_time: NumericTime::Unix
+		}
+	}
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ImportConfig {
+	pub mode: ImportMode,
+	/// Name of selected profile.
+	#[serde(default = "default_profile_name")]
+	pub profile: String,
+	#[serde(default = "default_profiles")]
+	pub profiles: Vec<ImportProfile>,
+	/// Add loaded file to current data instead of replacing it.
+	#[serde(default = "default_append")]
+	pub append: bool
+}
+
+fn default_append() -> bool {
+	true
+}
+
+fn default_profile_name() -> String {
+	String::from(DEFAULT_PROFILE)
+}
+
+fn default_profiles() -> Vec<ImportProfile> {
+	vec![ImportProfile::default()]
+}
+
+impl Default for ImportConfig {
+	fn default() -> Self {
+		Self {
+			mode: ImportMode::Strict,
+			profile: default_profile_name(),
+			profiles: default_profiles(),
+			append: default_append()
+		}
+	}
+}
+
+impl ImportConfig {
+	/// Selected profile, default one if not found.
+	pub fn selected(&self) -> ImportProfile {
+		match self.profiles.iter().find(|p| p.name == self.profile) {
+			Some(p) => p.clone(),
+			None => ImportProfile::default()
+		}
+	}
+
+	/// Replace profile with same name or add new one, and select it.
+	pub fn save_profile(&mut self, profile: ImportProfile) {
+		self.profile = profile.name.clone();
+		match self.profiles.iter_mut().find(|p| p.name == profile.name) {
+			Some(p) => *p = profile,
+			None => self.profiles.push(profile)
+		}
+	}
+
+	pub fn options(&self) -> ImportOptions {
+		ImportOptions {
+			mode: self.mode,
+			profile: self.selected(),
+			append: self.append,
+			channel: None
+		}
+	}
+}
+
+/// Everything loader needs to know about import.
+#[derive(Clone)]
+pub struct ImportOptions {
+	pub mode: ImportMode,
+	pub profile: ImportProfile,
+	pub append: bool,
+	/// Channel of per channel CSV, guessed from file name if not set.
+	pub channel: Option<Channel>
+}
+
+/// Column indices of one sheet.
+#[derive(Clone, Copy)]
+pub struct ColumnMap {
+	pub latitude: usize,
+	pub longitude: usize,
+	pub deep: usize,
+	pub datetime: usize,
+	pub solar: usize,
+	pub spectrum: usize,
+	pub temp: usize,
+	pub speed: usize,
+	pub dir: usize,
+	/// Found by header only.
+	pub channel: Option<usize>
+}
+
+impl Default for ColumnMap {
+	fn default() -> Self {
+		Self {
+			latitude: XLSX_LATITUDE_INDEX,
+			longitude: XLSX_LONGITUDE_INDEX,
+			deep: XLSX_DEEP_INDEX,
+			datetime: XLSX_DATETIME_INDEX,
+			solar: XLSX_SPEC_INDEX,
+			spectrum: XLSX_SPEC_INDEX + 1,
+			temp: XLSX_SPEC_INDEX,
+			speed: XLSX_SPEC_INDEX,
+			dir: XLSX_SPEC_INDEX + 1,
+			channel: None
+		}
+	}
+}
+
+fn normalize(name: &str) -> String {
+	name.trim().to_lowercase()
+}
+
+impl ColumnMap {
+	/// Columns found by header names, positional layout for the rest.
+	pub fn from_header(header: &[String], aliases: &ColumnAliases) -> Self {
+		let header: Vec<String> = header.iter().map(|h| normalize(h)).collect();
+		let position = |names: &Vec<String>| header.iter().position(|h| names.iter().any(|n| normalize(n) == *h));
+		let find = |names: &Vec<String>, def: usize| {
+			match position(names) {
+				Some(i) => i,
+				None => def
+			}
+		};
+		let def = Self::default();
+		Self {
+			latitude: find(&aliases.latitude, def.latitude),
+			longitude: find(&aliases.longitude, def.longitude),
+			deep: find(&aliases.deep, def.deep),
+			datetime: find(&aliases.datetime, def.datetime),
+			solar: find(&aliases.solar, def.solar),
+			spectrum: find(&aliases.spectrum, def.spectrum),
+			temp: find(&aliases.temp, def.temp),
+			speed: find(&aliases.speed, def.speed),
+			dir: find(&aliases.dir, def.dir),
+			channel: position(&aliases.channel)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// 2023-01-01 12:00:00 UTC.
+	const NOON: i64 = 1_672_574_400;
+	const MSK: i32 = 3 * 60;
+
+	fn noon() -> NaiveDateTime {
+		NaiveDate::from_ymd_opt(2023, 1, 1).unwrap().and_hms_opt(12, 0, 0).unwrap()
+	}
+
+	#[test]
+	fn rfc3339_with_offset() {
+		assert_eq!(parse_datetime("2023-01-01T15:00:00+03:00", SourceTimezone::Utc, 0), Some(NOON));
+		assert_eq!(parse_datetime("2023-01-01T12:00:00Z", SourceTimezone::Offset, MSK), Some(NOON));
+	}
+
+	#[test]
+	fn datetime_formats() {
+		for f in DATETIME_FORMATS {
+			let s = noon().format(f).to_string();
+			assert_eq!(parse_datetime(&s, SourceTimezone::Utc, 0), Some(NOON), "{}", s);
+			assert_eq!(parse_datetime(&s, SourceTimezone::Offset, MSK), Some(NOON - 3 * 3600), "{}", s);
+		}
+	}
+
+	#[test]
+	fn date_formats() {
+		let midnight = NOON - 12 * 3600;
+		for f in DATE_FORMATS {
+			let s = noon().format(f).to_string();
+			assert_eq!(parse_datetime(&s, SourceTimezone::Utc, 0), Some(midnight), "{}", s);
+			assert_eq!(parse_datetime(&s, SourceTimezone::Offset, MSK), Some(midnight - 3 * 3600), "{}", s);
+		}
+	}
+
+	#[test]
+	fn unix_seconds_string() {
+		assert_eq!(parse_datetime("1672574400", SourceTimezone::Offset, MSK), Some(NOON));
+		assert_eq!(parse_datetime(" 0 ", SourceTimezone::Utc, 0), Some(0));
+	}
+
+	#[test]
+	fn excel_serial() {
+		let epoch = excel_to_naive(25569.0).unwrap();
+		assert_eq!(local_to_timestamp(&epoch, SourceTimezone::Utc, 0), Some(0));
+		let noon_serial = excel_to_naive(44927.5).unwrap();
+		assert_eq!(noon_serial, noon());
+		assert_eq!(local_to_timestamp(&noon_serial, SourceTimezone::Offset, MSK), Some(NOON - 3 * 3600));
+	}
+
+	#[test]
+	fn numeric_time() {
+		assert_eq!(numeric_to_timestamp(NOON as f64, NumericTime::Unix, SourceTimezone::Utc, 0), Some(NOON));
+		assert_eq!(numeric_to_timestamp(44927.5, NumericTime::Unix, SourceTimezone::Utc, 0), None);
+		assert_eq!(numeric_to_timestamp(44927.5, NumericTime::Excel, SourceTimezone::Utc, 0), Some(NOON));
+		assert_eq!(numeric_to_timestamp(44927.5, NumericTime::Excel, SourceTimezone::Offset, MSK), Some(NOON - 3 * 3600));
+	}
+
+	fn header(names: &[&str]) -> Vec<String> {
+		names.iter().map(|n| String::from(*n)).collect()
+	}
+
+	#[test]
+	fn columns_by_aliases() {
+		let columns = ColumnMap::from_header(&header(&["Время", "Depth", " LAT ", "lon", "Temperature", "type"]), &ColumnAliases::default());
+		assert_eq!(columns.datetime, 0);
+		assert_eq!(columns.deep, 1);
+		assert_eq!(columns.latitude, 2);
+		assert_eq!(columns.longitude, 3);
+		assert_eq!(columns.temp, 4);
+		assert_eq!(columns.channel, Some(5));
+	}
+
+	#[test]
+	fn columns_positional_fallback() {
+		let columns = ColumnMap::from_header(&header(&["a", "b", "c", "d", "e", "f"]), &ColumnAliases::default());
+		let def = ColumnMap::default();
+		assert_eq!(columns.latitude, XLSX_LATITUDE_INDEX);
+		assert_eq!(columns.longitude, XLSX_LONGITUDE_INDEX);
+		assert_eq!(columns.deep, XLSX_DEEP_INDEX);
+		assert_eq!(columns.datetime, XLSX_DATETIME_INDEX);
+		assert_eq!(columns.solar, def.solar);
+		assert_eq!(columns.spectrum, XLSX_SPEC_INDEX + 1);
+		assert_eq!(columns.channel, None);
+		let columns = ColumnMap::from_header(&header(&["lat", "lon"]), &ColumnAliases::default());
+		assert_eq!(columns.deep, XLSX_DEEP_INDEX);
+	}
+
+	#[test]
+	fn spectrum_start_column() {
+		let columns = ColumnMap::from_header(&header(&["lat", "lon", "deep", "time", "solar", "note", "wavelength", "transparency"]), &ColumnAliases::default());
+		assert_eq!(columns.solar, 4);
+		assert_eq!(columns.spectrum, 6);
+		let mut aliases = ColumnAliases::default();
+		aliases.spectrum = vec![String::from("λ")];
+		let columns = ColumnMap::from_header(&header(&["lat", "lon", "deep", "time", "solar", "λ"]), &aliases);
+		assert_eq!(columns.spectrum, 5);
+	}
+
+	#[test]
+	fn garbage_is_rejected() {
+		assert_eq!(parse_datetime("yesterday", SourceTimezone::Utc, 0), None);
+		assert_eq!(parse_datetime("", SourceTimezone::Utc, 0), None);
+	}
+}
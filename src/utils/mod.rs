@@ -1,5 +1,13 @@
 pub mod config;
 pub mod data_loader;
+pub mod projection;
+pub mod data_index;
+pub mod columns;
+pub mod progress;
+pub mod data_error;
+pub mod import;
+
+use data_loader::Point;
 
 pub fn in_delta_f64(val1: f64, val2: f64, d: f64) -> bool {
 	let diff = val1 - val2;
@@ -9,4 +17,78 @@ pub fn in_delta_f64(val1: f64, val2: f64, d: f64) -> bool {
 pub fn in_delta_i64(val1: i64, val2: i64, d: i64) -> bool {
 	let diff = val1 - val2;
 	diff.abs() < d
-}
\ No newline at end of file
+}
+
+/// Ray casting test in lat/lon, polygon is closed implicitly, less than 3 vertices contain nothing.
+/// Boundary is half open, points on edges facing lower latitude or longitude are inside.
+pub fn in_polygon(p: &Point, poly: &[Point]) -> bool {
+	if poly.len() < 3 {
+		return false;
+	}
+	let mut inside = false;
+	let mut j = poly.len() - 1;
+	for i in 0..poly.len() {
+		let a = &poly[i];
+		let b = &poly[j];
+		if (a.latitude > p.latitude) != (b.latitude > p.latitude) {
+			let lon = a.longitude + (p.latitude - a.latitude) * (b.longitude - a.longitude) / (b.latitude - a.latitude);
+			if p.longitude < lon {
+				inside = !inside;
+			}
+		}
+		j = i;
+	}
+	inside
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn pt(latitude: f64, longitude: f64) -> Point {
+		Point {latitude: latitude, longitude: longitude, deep: 0.0}
+	}
+
+	fn square() -> Vec<Point> {
+		vec![pt(0.0, 0.0), pt(1.0, 0.0), pt(1.0, 1.0), pt(0.0, 1.0)]
+	}
+
+	#[test]
+	fn inside_and_outside() {
+		let sq = square();
+		assert!(in_polygon(&pt(0.5, 0.5), &sq));
+		assert!(in_polygon(&pt(0.01, 0.99), &sq));
+		assert!(!in_polygon(&pt(1.5, 0.5), &sq));
+		assert!(!in_polygon(&pt(0.5, -0.1), &sq));
+		assert!(!in_polygon(&pt(-0.5, -0.5), &sq));
+	}
+
+	#[test]
+	fn concave() {
+		// U shape open to high latitude, notch between longitudes 1 and 2.
+		let u = [pt(0.0, 0.0), pt(3.0, 0.0), pt(3.0, 1.0), pt(1.0, 1.0), pt(1.0, 2.0), pt(3.0, 2.0), pt(3.0, 3.0), pt(0.0, 3.0)];
+		assert!(in_polygon(&pt(0.5, 1.5), &u));
+		assert!(in_polygon(&pt(2.0, 0.5), &u));
+		assert!(in_polygon(&pt(2.0, 2.5), &u));
+		assert!(!in_polygon(&pt(2.0, 1.5), &u));
+		assert!(!in_polygon(&pt(3.5, 1.5), &u));
+	}
+
+	#[test]
+	fn on_boundary() {
+		// Half open: low latitude and longitude edges are inside, high ones outside.
+		let sq = square();
+		assert!(in_polygon(&pt(0.0, 0.0), &sq));
+		assert!(in_polygon(&pt(0.0, 0.5), &sq));
+		assert!(in_polygon(&pt(0.5, 0.0), &sq));
+		assert!(!in_polygon(&pt(1.0, 0.5), &sq));
+		assert!(!in_polygon(&pt(0.5, 1.0), &sq));
+		assert!(!in_polygon(&pt(1.0, 1.0), &sq));
+	}
+
+	#[test]
+	fn degenerate() {
+		assert!(!in_polygon(&pt(0.0, 0.0), &[]));
+		assert!(!in_polygon(&pt(0.5, 0.5), &square()[..2]));
+	}
+}
@@ -0,0 +1,62 @@
+use std::sync::{Mutex, atomic::{AtomicBool, AtomicUsize, Ordering}};
+
+/// Cancel of task was requested.
+pub struct Cancelled;
+
+/// Progress of background task, shared between task and ui.
+pub struct Progress {
+	stage: Mutex<String>,
+	done: AtomicUsize,
+	total: AtomicUsize,
+	cancel: AtomicBool
+}
+
+impl Default for Progress {
+	fn default() -> Self {
+		Self {
+			stage: Mutex::new(String::new()),
+			done: AtomicUsize::new(0),
+			total: AtomicUsize::new(0),
+			cancel: AtomicBool::new(false)
+		}
+	}
+}
+
+impl Progress {
+	/// Start new stage of `total` steps, zero if unknown.
+	pub fn stage(&self, name: &str, total: usize) {
+		*self.stage.lock().unwrap() = String::from(name);
+		self.done.store(0, Ordering::Relaxed);
+		self.total.store(total, Ordering::Relaxed);
+	}
+
+	/// Count one step, error if cancel requested.
+	pub fn step(&self) -> Result<(), Cancelled> {
+		self.done.fetch_add(1, Ordering::Relaxed);
+		self.check()
+	}
+
+	pub fn check(&self) -> Result<(), Cancelled> {
+		if self.is_cancelled() {
+			return Err(Cancelled);
+		}
+		Ok(())
+	}
+
+	pub fn cancel(&self) {
+		self.cancel.store(true, Ordering::Relaxed);
+	}
+
+	pub fn is_cancelled(&self) -> bool {
+		self.cancel.load(Ordering::Relaxed)
+	}
+
+	/// Stage name, steps done and total.
+	pub fn get(&self) -> (String, usize, usize) {
+		(
+			self.stage.lock().unwrap().clone(),
+			self.done.load(Ordering::Relaxed),
+			self.total.load(Ordering::Relaxed)
+		)
+	}
+}
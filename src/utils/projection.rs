@@ -0,0 +1,237 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{config::Config, data_loader::{Data, Point}};
+
+const EARTH_RADIUS: f64 = 6_371_008.8;
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+const UTM_K0: f64 = 0.9996;
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectionKind {
+	/// Local plate carrée scaled by cos of origin latitude.
+	Equirectangular,
+	/// Transverse mercator in zone of origin.
+	Utm
+}
+
+impl Default for ProjectionKind {
+	fn default() -> Self {
+		Self::Equirectangular
+	}
+}
+
+/// Maps geographic coordinates to world coordinates in meters relative to `origin` and back.
+#[derive(Resource, Clone)]
+pub struct Projection {
+	pub kind: ProjectionKind,
+	pub origin: Point,
+	origin_xy: (f64, f64),
+	utm_lon0: f64
+}
+
+impl Default for Projection {
+	fn default() -> Self {
+		Self::new(ProjectionKind::default(), &Point::default())
+	}
+}
+
+impl Projection {
+	pub fn new(kind: ProjectionKind, origin: &Point) -> Self {
+		let zone = utm_zone(origin.longitude);
+		let mut proj = Self {
+			kind: kind,
+			origin: origin.clone(),
+			origin_xy: (0.0, 0.0),
+			utm_lon0: (zone as f64 - 1.0) * 6.0 - 180.0 + 3.0
+		};
+		proj.origin_xy = proj.forward(origin.latitude, origin.longitude);
+		proj
+	}
+
+	pub fn for_data(kind: ProjectionKind, data: &Data) -> Self {
+		Self::new(kind, &centroid(data))
+	}
+
+	pub fn to_world(&self, p: &Point) -> Vec2 {
+		let (x, y) = self.forward(p.latitude, p.longitude);
+		Vec2::new((x - self.origin_xy.0) as f32, (y - self.origin_xy.1) as f32)
+	}
+
+	/// Inverse of `to_world`, resulting deep is 0.
+	pub fn to_point(&self, v: Vec2) -> Point {
+		let x = v.x as f64 + self.origin_xy.0;
+		let y = v.y as f64 + self.origin_xy.1;
+		let (latitude, longitude) = self.inverse(x, y);
+		Point {
+			latitude: latitude,
+			longitude: longitude,
+			deep: 0.0
+		}
+	}
+
+	fn forward(&self, lat: f64, lon: f64) -> (f64, f64) {
+		match self.kind {
+			ProjectionKind::Equirectangular => (
+				EARTH_RADIUS * lon.to_radians() * self.origin.latitude.to_radians().cos(),
+				EARTH_RADIUS * lat.to_radians()
+			),
+			ProjectionKind::Utm => utm_forward(lat, lon, self.utm_lon0)
+		}
+	}
+
+	fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+		match self.kind {
+			ProjectionKind::Equirectangular => (
+				(y / EARTH_RADIUS).to_degrees(),
+				(x / (EARTH_RADIUS * self.origin.latitude.to_radians().cos())).to_degrees()
+			),
+			ProjectionKind::Utm => utm_inverse(x, y, self.utm_lon0)
+		}
+	}
+}
+
+pub fn utm_zone(lon: f64) -> u8 {
+	let zone = ((lon + 180.0) / 6.0).floor() as i64 + 1;
+	zone.clamp(1, 60) as u8
+}
+
+/// Mean position of border and all measurements.
+pub fn centroid(data: &Data) -> Point {
+	let points = data.points();
+	let mut lat = 0.0;
+	let mut lon = 0.0;
+	let mut n = 0;
+	for p in points {
+		lat += p.latitude;
+		lon += p.longitude;
+		n += 1;
+	}
+	if n == 0 {
+		return Point::default();
+	}
+	Point {
+		latitude: lat / n as f64,
+		longitude: lon / n as f64,
+		deep: 0.0
+	}
+}
+
+/// Easting and northing without false offsets.
+fn utm_forward(lat: f64, lon: f64, lon0: f64) -> (f64, f64) {
+	let e2 = WGS84_F * (2.0 - WGS84_F);
+	let ep2 = e2 / (1.0 - e2);
+	let phi = lat.to_radians();
+	let (sin, cos) = phi.sin_cos();
+	let n = WGS84_A / (1.0 - e2 * sin * sin).sqrt();
+	let t = phi.tan().powi(2);
+	let c = ep2 * cos * cos;
+	let a = cos * (lon - lon0).to_radians();
+	let m = meridian_arc(phi, e2);
+	let x = UTM_K0 * n * (a
+		+ (1.0 - t + c) * a.powi(3) / 6.0
+		+ (5.0 - 18.0*t + t*t + 72.0*c - 58.0*ep2) * a.powi(5) / 120.0);
+	let y = UTM_K0 * (m + n * phi.tan() * (a*a / 2.0
+		+ (5.0 - t + 9.0*c + 4.0*c*c) * a.powi(4) / 24.0
+		+ (61.0 - 58.0*t + t*t + 600.0*c - 330.0*ep2) * a.powi(6) / 720.0));
+	(x, y)
+}
+
+fn utm_inverse(x: f64, y: f64, lon0: f64) -> (f64, f64) {
+	let e2 = WGS84_F * (2.0 - WGS84_F);
+	let ep2 = e2 / (1.0 - e2);
+	let e4 = e2 * e2;
+	let e6 = e4 * e2;
+	let m = y / UTM_K0;
+	let mu = m / (WGS84_A * (1.0 - e2/4.0 - 3.0*e4/64.0 - 5.0*e6/256.0));
+	let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+	let phi1 = mu
+		+ (3.0*e1/2.0 - 27.0*e1.powi(3)/32.0) * (2.0*mu).sin()
+		+ (21.0*e1*e1/16.0 - 55.0*e1.powi(4)/32.0) * (4.0*mu).sin()
+		+ (151.0*e1.powi(3)/96.0) * (6.0*mu).sin()
+		+ (1097.0*e1.powi(4)/512.0) * (8.0*mu).sin();
+	let (sin1, cos1) = phi1.sin_cos();
+	let n1 = WGS84_A / (1.0 - e2 * sin1 * sin1).sqrt();
+	let t1 = phi1.tan().powi(2);
+	let c1 = ep2 * cos1 * cos1;
+	let r1 = WGS84_A * (1.0 - e2) / (1.0 - e2 * sin1 * sin1).powf(1.5);
+	let d = x / (n1 * UTM_K0);
+	let phi = phi1 - (n1 * phi1.tan() / r1) * (d*d / 2.0
+		- (5.0 + 3.0*t1 + 10.0*c1 - 4.0*c1*c1 - 9.0*ep2) * d.powi(4) / 24.0
+		+ (61.0 + 90.0*t1 + 298.0*c1 + 45.0*t1*t1 - 252.0*ep2 - 3.0*c1*c1) * d.powi(6) / 720.0);
+	let lon = (d
+		- (1.0 + 2.0*t1 + c1) * d.powi(3) / 6.0
+		+ (5.0 - 2.0*c1 + 28.0*t1 - 3.0*c1*c1 + 8.0*ep2 + 24.0*t1*t1) * d.powi(5) / 120.0) / cos1;
+	(phi.to_degrees(), lon0 + lon.to_degrees())
+}
+
+fn meridian_arc(phi: f64, e2: f64) -> f64 {
+	let e4 = e2 * e2;
+	let e6 = e4 * e2;
+	WGS84_A * ((1.0 - e2/4.0 - 3.0*e4/64.0 - 5.0*e6/256.0) * phi
+		- (3.0*e2/8.0 + 3.0*e4/32.0 + 45.0*e6/1024.0) * (2.0*phi).sin()
+		+ (15.0*e4/256.0 + 45.0*e6/1024.0) * (4.0*phi).sin()
+		- (35.0*e6/3072.0) * (6.0*phi).sin())
+}
+
+pub fn update_projection(data: Res<Data>, config: Res<Config>, mut proj: ResMut<Projection>) {
+	if data.is_changed() || config.projection != proj.kind {
+		*proj = Projection::for_data(config.projection, &data);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// About 1 cm on ground.
+	const TOLERANCE_DEG: f64 = 1e-7;
+
+	fn point(latitude: f64, longitude: f64) -> Point {
+		Point {
+			latitude: latitude,
+			longitude: longitude,
+			deep: 0.0
+		}
+	}
+
+	fn assert_round_trip(kind: ProjectionKind, origin: Point, points: &[(f64, f64)]) {
+		let proj = Projection::new(kind, &origin);
+		for &(lat, lon) in points {
+			let back = proj.to_point(proj.to_world(&point(lat, lon)));
+			assert!(
+				(back.latitude - lat).abs() < TOLERANCE_DEG && (back.longitude - lon).abs() < TOLERANCE_DEG,
+				"({}, {}) returned as ({}, {})", lat, lon, back.latitude, back.longitude
+			);
+		}
+	}
+
+	const NEAR_ORIGIN: [(f64, f64); 4] = [(0.0, 0.0), (0.01, 0.01), (-0.02, 0.015), (0.005, -0.03)];
+	/// Zone 31 ends at 6° east.
+	const ZONE_EDGE: [(f64, f64); 4] = [(52.0, 5.9), (52.0, 6.0), (52.05, 6.1), (51.95, 6.2)];
+	const SOUTH: [(f64, f64); 4] = [(-33.9, 18.4), (-33.95, 18.45), (-33.85, 18.3), (-34.0, 18.5)];
+
+	#[test]
+	fn equirectangular_round_trip() {
+		assert_round_trip(ProjectionKind::Equirectangular, point(0.0, 0.0), &NEAR_ORIGIN);
+		assert_round_trip(ProjectionKind::Equirectangular, point(52.0, 5.95), &ZONE_EDGE);
+		assert_round_trip(ProjectionKind::Equirectangular, point(-33.9, 18.4), &SOUTH);
+	}
+
+	#[test]
+	fn utm_round_trip() {
+		assert_round_trip(ProjectionKind::Utm, point(0.0, 0.0), &NEAR_ORIGIN);
+		assert_round_trip(ProjectionKind::Utm, point(52.0, 5.95), &ZONE_EDGE);
+		assert_round_trip(ProjectionKind::Utm, point(-33.9, 18.4), &SOUTH);
+	}
+
+	#[test]
+	fn utm_zone_edge() {
+		assert_eq!(utm_zone(5.999), 31);
+		assert_eq!(utm_zone(6.0), 32);
+		assert_eq!(utm_zone(-180.0), 1);
+		assert_eq!(utm_zone(180.0), 60);
+	}
+}